@@ -3,6 +3,7 @@ use crate::{color::Color, mask::Mask, state::State};
 pub struct Capture<'a> {
     attacker: &'a mut Mask,
     defender: &'a mut Mask,
+    captured: Mask,
 }
 
 impl<'a> Capture<'a> {
@@ -22,18 +23,58 @@ impl<'a> Capture<'a> {
             }
         };
 
-        Self { attacker, defender }
+        Self {
+            attacker,
+            defender,
+            captured: Mask::EMPTY,
+        }
     }
 
     pub fn try_capture(&mut self, x: usize, y: usize) -> bool {
+        let Some(group) = self.would_capture_mask(x, y) else {
+            return false;
+        };
+        for (row, &mask) in self.defender.rows_mut().zip(group.rows()) {
+            *row &= !mask;
+        }
+        self.captured |= group;
+        true
+    }
+
+    #[must_use]
+    pub fn would_capture(&self, x: usize, y: usize) -> bool {
+        self.would_capture_mask(x, y).is_some()
+    }
+
+    #[must_use]
+    pub fn would_capture_mask(&self, x: usize, y: usize) -> Option<Mask> {
+        if !self.defender.get(x, y) {
+            return None;
+        }
         let group = self.defender.flood(x, y);
-        let is_capture = !group.has_a_liberty(self.attacker);
-        if is_capture {
-            for (row, &mask) in self.defender.rows_mut().zip(group.rows()) {
-                *row &= !mask;
-            }
+        (!group.has_a_liberty(self.attacker)).then_some(group)
+    }
+
+    pub fn try_capture_neighbors(&mut self, x: usize, y: usize) -> u32 {
+        let before = self.captured.count();
+        if x > 0 {
+            self.try_capture(x - 1, y);
+        }
+        if x < 18 {
+            self.try_capture(x + 1, y);
         }
-        is_capture
+        if y > 0 {
+            self.try_capture(x, y - 1);
+        }
+        if y < 18 {
+            self.try_capture(x, y + 1);
+        }
+        self.captured.count() - before
+    }
+
+    #[must_use]
+    pub fn captured(&self) -> &Mask {
+        &self.captured
     }
 }
 
@@ -58,7 +99,7 @@ mod tests {
             0b000,
         ]);
 
-        let mut state = State::new(black, white);
+        let mut state = State::from_masks(black, white).unwrap();
         let mut capture = Capture::new(&mut state, Color::Black);
         assert!(capture.try_capture(1, 1));
         assert_eq!(state.black, black);
@@ -85,7 +126,7 @@ mod tests {
             0b00000,
         ]);
 
-        let mut state = State::new(black, white);
+        let mut state = State::from_masks(black, white).unwrap();
         let mut capture = Capture::new(&mut state, Color::Black);
         assert!(capture.try_capture(1, 1));
         assert_eq!(state.black, black);
@@ -112,10 +153,64 @@ mod tests {
             0b00000,
         ]);
 
-        let mut state = State::new(black, white);
+        let mut state = State::from_masks(black, white).unwrap();
         let mut capture = Capture::new(&mut state, Color::Black);
         assert!(!capture.try_capture(1, 1));
         assert_eq!(state.black, black);
         assert_eq!(state.white, white);
     }
+
+    #[test]
+    fn captures_all_neighbors() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b01010,
+            0b10001,
+            0b01010,
+            0b00100,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b00100,
+            0b01010,
+            0b00100,
+        ]);
+
+        let mut state = State::from_masks(black, white).unwrap();
+        state.set(2, 1, Some(Color::Black));
+        let mut capture = Capture::new(&mut state, Color::Black);
+        assert_eq!(capture.try_capture_neighbors(2, 1), 4);
+        assert_eq!(capture.captured(), &white);
+        assert_eq!(state.white, Mask::EMPTY);
+    }
+
+    #[test]
+    fn ignores_non_defender_neighbors() {
+        let black = Mask::new([0b011]);
+        let mut state = State::from_masks(black, Mask::EMPTY).unwrap();
+        let mut capture = Capture::new(&mut state, Color::Black);
+        assert!(!capture.try_capture(2, 0));
+        assert!(!capture.try_capture(1, 0));
+        assert_eq!(capture.try_capture_neighbors(1, 0), 0);
+    }
+
+    #[test]
+    fn would_capture_does_not_modify() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b010,
+            0b101,
+            0b010,
+        ]);
+        let white = Mask::new([0b000, 0b010]);
+
+        let mut state = State::from_masks(black, white).unwrap();
+        let capture = Capture::new(&mut state, Color::Black);
+        assert!(capture.would_capture(1, 1));
+        assert_eq!(capture.would_capture_mask(1, 1), Some(white));
+        assert!(!capture.would_capture(0, 0));
+        assert_eq!(capture.would_capture_mask(1, 0), None);
+        assert_eq!(state.white, white);
+    }
 }
@@ -1,3 +1,8 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Index, IndexMut},
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
     Black,
@@ -5,10 +10,111 @@ pub enum Color {
 }
 
 impl Color {
+    #[inline]
+    #[must_use]
     pub fn opposite(self) -> Self {
         match self {
             Self::Black => Self::White,
             Self::White => Self::Black,
         }
     }
+
+    #[inline]
+    #[must_use]
+    pub fn opponent(self) -> Self {
+        self.opposite()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn ally(self) -> Self {
+        self
+    }
+
+    #[must_use]
+    pub fn index(self) -> usize {
+        match self {
+            Self::Black => 0,
+            Self::White => 1,
+        }
+    }
+
+    #[must_use]
+    pub fn from_index(i: usize) -> Option<Self> {
+        match i {
+            0 => Some(Self::Black),
+            1 => Some(Self::White),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Black => write!(f, "Black"),
+            Self::White => write!(f, "White"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorArray<T>(pub [T; 2]);
+
+impl<T> ColorArray<T> {
+    pub fn new_with(f: impl Fn(Color) -> T) -> Self {
+        Self([f(Color::Black), f(Color::White)])
+    }
+}
+
+impl<T> Index<Color> for ColorArray<T> {
+    type Output = T;
+
+    fn index(&self, color: Color) -> &Self::Output {
+        &self.0[color.index()]
+    }
+}
+
+impl<T> IndexMut<Color> for ColorArray<T> {
+    fn index_mut(&mut self, color: Color) -> &mut Self::Output {
+        &mut self.0[color.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_round_trip() {
+        for color in [Color::Black, Color::White] {
+            assert_eq!(Color::from_index(color.index()), Some(color));
+        }
+        assert_eq!(Color::Black.index(), 0);
+        assert_eq!(Color::White.index(), 1);
+        assert_eq!(Color::from_index(2), None);
+    }
+
+    #[test]
+    fn opponent_and_ally() {
+        for color in [Color::Black, Color::White] {
+            assert_eq!(color.opponent(), color.opposite());
+            assert_eq!(color.ally(), color);
+            assert_eq!(color.opponent().opponent(), color.ally());
+        }
+        assert_eq!(Color::Black.opponent(), Color::White);
+    }
+
+    #[test]
+    fn color_array() {
+        let mut scores = ColorArray([0u32; 2]);
+        scores[Color::Black] += 5;
+        scores[Color::White] += 2;
+        assert_eq!(scores[Color::Black], 5);
+        assert_eq!(scores, ColorArray([5, 2]));
+
+        let names = ColorArray::new_with(|color| color.to_string());
+        assert_eq!(names[Color::White], "White");
+        assert_eq!(ColorArray::<u8>::default(), ColorArray([0, 0]));
+    }
 }
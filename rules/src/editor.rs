@@ -0,0 +1,103 @@
+use crate::{Color, State, Tree};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BoardEditor {
+    state: State,
+}
+
+impl BoardEditor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn from_state(state: State) -> Self {
+        Self { state }
+    }
+
+    pub fn place_black(&mut self, x: usize, y: usize) -> Result<(), EditorError> {
+        self.place(x, y, Color::Black)
+    }
+
+    pub fn place_white(&mut self, x: usize, y: usize) -> Result<(), EditorError> {
+        self.place(x, y, Color::White)
+    }
+
+    fn place(&mut self, x: usize, y: usize, color: Color) -> Result<(), EditorError> {
+        if let Some(existing) = self.state.color_at(x, y) {
+            return Err(EditorError::Occupied { x, y, existing });
+        }
+        self.state.set(x, y, Some(color));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, x: usize, y: usize) {
+        self.state.set(x, y, None);
+    }
+
+    pub fn clear(&mut self) {
+        self.state = State::default();
+    }
+
+    #[must_use]
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    #[must_use]
+    pub fn build(self, to_play: Color) -> Tree {
+        Tree::from_state(self.state, to_play)
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum EditorError {
+    #[error("Intersection ({x}, {y}) is already occupied by {existing}")]
+    Occupied { x: usize, y: usize, existing: Color },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_stones_without_rules() {
+        let mut editor = BoardEditor::new();
+        // A stone with no liberties is fine when setting up a position
+        editor.place_white(0, 0).unwrap();
+        editor.place_black(1, 0).unwrap();
+        editor.place_black(0, 1).unwrap();
+
+        let tree = editor.build(Color::White);
+        assert_eq!(tree.current().color_at(0, 0), Some(Color::White));
+        assert_eq!(tree.current().black.count(), 2);
+        assert_eq!(tree.to_play(), Color::White);
+        assert_eq!(tree.node_count(), 1);
+    }
+
+    #[test]
+    fn rejects_overlap() {
+        let mut editor = BoardEditor::new();
+        editor.place_black(3, 3).unwrap();
+        let error = editor.place_white(3, 3).unwrap_err();
+        assert_eq!(
+            error,
+            EditorError::Occupied {
+                x: 3,
+                y: 3,
+                existing: Color::Black
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Intersection (3, 3) is already occupied by Black"
+        );
+        assert_eq!(editor.state().color_at(3, 3), Some(Color::Black));
+
+        editor.remove(3, 3);
+        editor.place_white(3, 3).unwrap();
+        editor.clear();
+        assert_eq!(editor.state(), &State::default());
+    }
+}
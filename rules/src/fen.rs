@@ -0,0 +1,79 @@
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum FenError {
+    #[error("Expected 19 rows but found {0}")]
+    RowCount(usize),
+    #[error("Row {0} does not describe exactly 19 intersections")]
+    RowLength(usize),
+    #[error("Row {0} has a run with a leading zero")]
+    ZeroRun(usize),
+    #[error("Unexpected character '{0}'")]
+    InvalidCharacter(char),
+}
+
+pub(crate) fn encode(stone_at: impl Fn(usize, usize) -> Option<char>) -> String {
+    let mut out = String::new();
+    for y in 0..19 {
+        if y > 0 {
+            out.push('/');
+        }
+        let mut run = 0;
+        for x in 0..19 {
+            match stone_at(x, y) {
+                Some(c) => {
+                    if run > 0 {
+                        out.push_str(&run.to_string());
+                        run = 0;
+                    }
+                    out.push(c);
+                }
+                None => run += 1,
+            }
+        }
+        if run > 0 {
+            out.push_str(&run.to_string());
+        }
+    }
+    out
+}
+
+pub(crate) fn decode(
+    fen: &str,
+    mut place: impl FnMut(usize, usize, char) -> Result<(), FenError>,
+) -> Result<(), FenError> {
+    let rows: Vec<_> = fen.split('/').collect();
+    if rows.len() != 19 {
+        return Err(FenError::RowCount(rows.len()));
+    }
+
+    for (y, row) in rows.into_iter().enumerate() {
+        let mut x = 0;
+        let mut run = 0;
+        for c in row.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                // Runs are never empty, so each board has a single encoding
+                if run == 0 && digit == 0 {
+                    return Err(FenError::ZeroRun(y));
+                }
+                run = run * 10 + digit as usize;
+                // Bail out before a long run of digits can overflow
+                if x + run > 19 {
+                    return Err(FenError::RowLength(y));
+                }
+                continue;
+            }
+            x += run;
+            run = 0;
+            if x > 18 {
+                return Err(FenError::RowLength(y));
+            }
+            place(x, y, c)?;
+            x += 1;
+        }
+        x += run;
+        if x != 19 {
+            return Err(FenError::RowLength(y));
+        }
+    }
+
+    Ok(())
+}
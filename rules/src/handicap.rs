@@ -0,0 +1,76 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handicap;
+
+const TWO: [(usize, usize); 2] = [(3, 3), (15, 15)];
+const THREE: [(usize, usize); 3] = [(3, 3), (15, 15), (3, 15)];
+const FOUR: [(usize, usize); 4] = [(3, 3), (15, 15), (3, 15), (15, 3)];
+const FIVE: [(usize, usize); 5] = [(3, 3), (15, 15), (3, 15), (15, 3), (9, 9)];
+const SIX: [(usize, usize); 6] = [(3, 3), (15, 15), (3, 15), (15, 3), (3, 9), (15, 9)];
+const SEVEN: [(usize, usize); 7] = [(3, 3), (15, 15), (3, 15), (15, 3), (9, 9), (3, 9), (15, 9)];
+const EIGHT: [(usize, usize); 8] = [
+    (3, 3),
+    (15, 15),
+    (3, 15),
+    (15, 3),
+    (3, 9),
+    (15, 9),
+    (9, 3),
+    (9, 15),
+];
+const NINE: [(usize, usize); 9] = [
+    (3, 3),
+    (15, 15),
+    (3, 15),
+    (15, 3),
+    (9, 9),
+    (3, 9),
+    (15, 9),
+    (9, 3),
+    (9, 15),
+];
+
+impl Handicap {
+    pub fn stones(n: usize) -> Result<&'static [(usize, usize)], HandicapError> {
+        match n {
+            2 => Ok(&TWO),
+            3 => Ok(&THREE),
+            4 => Ok(&FOUR),
+            5 => Ok(&FIVE),
+            6 => Ok(&SIX),
+            7 => Ok(&SEVEN),
+            8 => Ok(&EIGHT),
+            9 => Ok(&NINE),
+            _ => Err(HandicapError::InvalidCount(n)),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum HandicapError {
+    #[error("Handicap must be between 2 and 9 stones, got {0}")]
+    InvalidCount(usize),
+    #[error("A handicap point is already occupied")]
+    Occupied,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stone_counts() {
+        for n in 2..=9 {
+            assert_eq!(Handicap::stones(n).unwrap().len(), n);
+        }
+        assert_eq!(Handicap::stones(1), Err(HandicapError::InvalidCount(1)));
+        assert_eq!(Handicap::stones(10), Err(HandicapError::InvalidCount(10)));
+    }
+
+    #[test]
+    fn center_only_on_odd_counts() {
+        for n in 2..=9 {
+            let has_center = Handicap::stones(n).unwrap().contains(&(9, 9));
+            assert_eq!(has_center, n >= 5 && n % 2 == 1);
+        }
+    }
+}
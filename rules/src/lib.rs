@@ -1,24 +1,59 @@
+#![warn(clippy::must_use_candidate)]
+
 mod capture;
 mod color;
+mod editor;
+mod fen;
+mod handicap;
 mod mask;
 mod mask_row;
+#[cfg(feature = "rand")]
+mod players;
+mod score;
+mod sgf;
 mod state;
-
-use color::Color;
-use state::State;
-use std::fmt::Debug;
-
-use crate::capture::Capture;
+mod zobrist;
+
+pub use capture::Capture;
+pub use color::{Color, ColorArray};
+pub use editor::{BoardEditor, EditorError};
+pub use fen::FenError;
+pub use handicap::{Handicap, HandicapError};
+pub use mask::{Mask, MaskBitScanIter, MaskDecodeError};
+pub use mask_row::MaskRow;
+#[cfg(feature = "rand")]
+pub use players::RandomPlayer;
+pub use score::Score;
+pub use sgf::SgfError;
+pub use state::{SemeaiInfo, State, StateError};
+pub use zobrist::ZobristTable;
+
+use std::fmt::{self, Debug, Display, Formatter};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct Node {
     state: State,
     parent: usize,
+    captures: u32,
+    mv: Option<(Color, Move)>,
+    to_play: Color,
 }
 
 impl Node {
-    pub fn new(state: State, parent: usize) -> Self {
-        Self { state, parent }
+    pub fn new(
+        state: State,
+        parent: usize,
+        captures: u32,
+        mv: Option<(Color, Move)>,
+        to_play: Color,
+    ) -> Self {
+        Self {
+            state,
+            parent,
+            captures,
+            mv,
+            to_play,
+        }
     }
 }
 
@@ -26,98 +61,394 @@ impl Node {
 pub enum PlacementMode {
     Black,
     White,
-    Toggle,
+    Alternating,
+}
+
+impl PlacementMode {
+    #[deprecated(note = "Renamed to `PlacementMode::Alternating`")]
+    #[allow(non_upper_case_globals)]
+    pub const Toggle: Self = Self::Alternating;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum KoRule {
+    #[default]
+    Simple,
+    Superko,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Move {
+    Place(usize, usize),
+    Pass,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default)]
+pub struct NodeAnnotation {
+    pub comment: Option<String>,
+    pub evaluation: Option<f32>,
+}
+
+// Floats compare by bit pattern so that equality stays reflexive and `Eq` holds
+impl PartialEq for NodeAnnotation {
+    fn eq(&self, other: &Self) -> bool {
+        self.comment == other.comment
+            && self.evaluation.map(f32::to_bits) == other.evaluation.map(f32::to_bits)
+    }
+}
+
+impl Eq for NodeAnnotation {}
+
+#[derive(Debug, Clone)]
 pub struct Tree {
     nodes: Vec<Node>,
+    annotations: Vec<Option<NodeAnnotation>>,
+    hashes: Vec<Option<u64>>,
     current: usize,
-    to_play: Color,
     pub placement_mode: PlacementMode,
+    pub ko_rule: KoRule,
+    pub komi: f32,
 }
 
 impl Tree {
+    #[must_use]
     pub fn new(state: State, to_play: Color) -> Self {
+        Self::from_state(state, to_play)
+    }
+
+    #[must_use]
+    pub fn from_state(state: State, to_play: Color) -> Self {
         Self {
-            nodes: vec![Node::new(state, usize::MAX)],
+            nodes: vec![Node::new(state, usize::MAX, 0, None, to_play)],
+            annotations: vec![None],
+            hashes: vec![None],
             current: 0,
-            to_play,
-            placement_mode: PlacementMode::Toggle,
+            placement_mode: PlacementMode::Alternating,
+            ko_rule: KoRule::Simple,
+            komi: 0.0,
         }
     }
 
+    #[must_use]
     pub fn empty() -> Self {
-        Self {
-            nodes: vec![Node::new(State::default(), usize::MAX)],
-            current: 0,
-            to_play: Color::Black,
-            placement_mode: PlacementMode::Toggle,
-        }
+        Self::from_state(State::default(), Color::Black)
     }
 
-    pub fn place_stone(&mut self, x: usize, y: usize) -> Result<(), PlaceStoneError> {
-        assert!(x <= 18);
-        assert!(y <= 18);
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut tree = Self::empty();
+        tree.nodes.reserve(capacity.saturating_sub(1));
+        tree.annotations.reserve(capacity.saturating_sub(1));
+        tree.hashes.reserve(capacity.saturating_sub(1));
+        tree
+    }
 
-        let current_node = self.nodes[self.current];
-        let mut state = current_node.state.clone();
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
 
-        if state.black.get(x, y) || state.white.get(x, y) {
-            return Err(PlaceStoneError::AlreadyExists);
+    #[must_use]
+    pub fn memory_usage_bytes(&self) -> usize {
+        let comments: usize = self
+            .annotations
+            .iter()
+            .flatten()
+            .filter_map(|annotation| annotation.comment.as_ref())
+            .map(String::capacity)
+            .sum();
+        std::mem::size_of::<Tree>()
+            + self.nodes.capacity() * std::mem::size_of::<Node>()
+            + self.annotations.capacity() * std::mem::size_of::<Option<NodeAnnotation>>()
+            + self.hashes.capacity() * std::mem::size_of::<Option<u64>>()
+            + comments
+    }
+
+    pub fn replay(moves: impl IntoIterator<Item = Move>) -> Result<Self, (usize, PlaceStoneError)> {
+        let mut tree = Self::empty();
+        tree.apply_all(moves)?;
+        Ok(tree)
+    }
+
+    pub fn from_state_and_moves(
+        state: State,
+        to_play: Color,
+        moves: &[Move],
+    ) -> Result<Self, (usize, PlaceStoneError)> {
+        let mut tree = Self::from_state(state, to_play);
+        tree.apply_all(moves.iter().copied())?;
+        Ok(tree)
+    }
+
+    fn apply_all(
+        &mut self,
+        moves: impl IntoIterator<Item = Move>,
+    ) -> Result<(), (usize, PlaceStoneError)> {
+        for (i, mv) in moves.into_iter().enumerate() {
+            self.apply(mv).map_err(|error| (i, error))?;
         }
+        Ok(())
+    }
 
-        state.set(x, y, Some(self.to_play));
-
-        let mut capture = Capture::new(&mut state, self.to_play);
-        let mut is_capture = false;
-        is_capture |= x > 0 && capture.try_capture(x - 1, y);
-        is_capture |= x < 18 && capture.try_capture(x + 1, y);
-        is_capture |= y > 0 && capture.try_capture(x, y - 1);
-        is_capture |= y < 18 && capture.try_capture(x, y + 1);
-
-        if !is_capture {
-            let defender = Some(self.to_play.opposite());
-            let left = x == 0 || state.get(x - 1, y) == defender;
-            let right = x == 18 || state.get(x + 1, y) == defender;
-            let down = y == 0 || state.get(x, y - 1) == defender;
-            let up = y == 18 || state.get(x, y + 1) == defender;
-            let is_self_capture = left && right && down && up;
-            if is_self_capture {
-                return Err(PlaceStoneError::SelfCapture);
+    pub fn apply(&mut self, mv: Move) -> Result<(), PlaceStoneError> {
+        match mv {
+            Move::Place(x, y) => self.place_stone(x, y),
+            Move::Pass => {
+                self.pass();
+                Ok(())
             }
         }
+    }
 
-        if let Some(parent) = self.nodes.get(current_node.parent) {
-            if parent.state == state {
-                return Err(PlaceStoneError::Ko);
-            }
+    pub fn pass(&mut self) {
+        let state = self.nodes[self.current].state;
+        self.push_move(state, 0, Move::Pass);
+    }
+
+    pub fn place_stone(&mut self, x: usize, y: usize) -> Result<(), PlaceStoneError> {
+        let (state, captures) = self.play(x, y)?;
+        self.push_move(state, captures, Move::Place(x, y));
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let legal = self
+            .current()
+            .legal_moves_mask(self.to_play(), self.previous());
+        legal
+            .iter_positions()
+            .filter(|&(x, y)| self.ko_rule == KoRule::Simple || self.play(x, y).is_ok())
+            .map(|(x, y)| Move::Place(x, y))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn is_ko_position(&self, state: &State) -> bool {
+        match self.ko_rule {
+            KoRule::Simple => self.previous() == Some(state),
+            KoRule::Superko => self.has_repeated_position(state),
         }
+    }
+
+    #[must_use]
+    pub fn has_repeated_position(&self, state: &State) -> bool {
+        self.ancestors(self.current)
+            .any(|node| &node.state == state)
+    }
 
-        self.nodes.push(Node::new(state, self.current));
+    // Includes stones that were later captured, as well as setup stones
+    #[must_use]
+    pub fn stones_ever_placed_by(&self, color: Color) -> Mask {
+        self.ancestors(self.current)
+            .fold(Mask::EMPTY, |acc, node| acc | *node.state.stones(color))
+    }
+
+    fn play(&self, x: usize, y: usize) -> Result<(State, u32), PlaceStoneError> {
+        let mut state = *self.current();
+        let captures = state.apply_move(x, y, self.to_play(), None)?;
+        if self.is_ko_position(&state) {
+            return Err(PlaceStoneError::Ko);
+        }
+        Ok((state, captures))
+    }
+
+    // Walks from the node at `index` up to and including the root
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = &Node> {
+        self.ancestor_indices(index).map(|index| &self.nodes[index])
+    }
+
+    fn ancestor_indices(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let is_node = |index: usize| (index < self.nodes.len()).then_some(index);
+        std::iter::successors(is_node(index), move |&index| {
+            is_node(self.nodes[index].parent)
+        })
+    }
+
+    fn previous(&self) -> Option<&State> {
+        let parent = self.nodes[self.current].parent;
+        self.nodes.get(parent).map(|node| &node.state)
+    }
+
+    fn push_node(
+        &mut self,
+        state: State,
+        captures: u32,
+        mv: Option<(Color, Move)>,
+        to_play: Color,
+    ) {
+        self.nodes
+            .push(Node::new(state, self.current, captures, mv, to_play));
+        self.annotations.push(None);
+        self.hashes.push(None);
         self.current = self.nodes.len() - 1;
-        if self.placement_mode == PlacementMode::Toggle {
-            self.to_play = self.to_play.opposite();
+    }
+
+    fn push_move(&mut self, state: State, captures: u32, mv: Move) {
+        let color = self.to_play();
+        let to_play = self.to_play_after_move(&mv);
+        self.push_node(state, captures, Some((color, mv)), to_play);
+    }
+
+    // Passes hand over the turn exactly like stone placements do
+    #[must_use]
+    pub fn to_play_after_move(&self, _mv: &Move) -> Color {
+        match self.placement_mode {
+            PlacementMode::Alternating => self.to_play().opposite(),
+            PlacementMode::Black | PlacementMode::White => self.to_play(),
         }
+    }
 
-        Ok(())
+    pub fn annotate_current(&mut self, annotation: NodeAnnotation) {
+        self.annotations[self.current] = Some(annotation);
+    }
+
+    #[must_use]
+    pub fn annotation_at(&self, index: usize) -> Option<&NodeAnnotation> {
+        self.annotations.get(index)?.as_ref()
     }
 
+    #[must_use]
+    pub fn last_capture_count(&self) -> u32 {
+        self.nodes[self.current].captures
+    }
+
+    #[must_use]
     pub fn current(&self) -> &State {
         &self.nodes[self.current].state
     }
+
+    // Returning to an earlier node lets the next move start a variation
+    pub fn go_to(&mut self, index: usize) {
+        assert!(index < self.nodes.len());
+        self.current = index;
+    }
+
+    pub fn place_handicap(&mut self, n: usize) -> Result<(), HandicapError> {
+        let stones = Handicap::stones(n)?;
+        let mut state = *self.current();
+        if stones.iter().any(|&(x, y)| state.color_at(x, y).is_some()) {
+            return Err(HandicapError::Occupied);
+        }
+        state.set_multiple(stones.iter().map(|&(x, y)| (x, y, Some(Color::Black))));
+        self.push_node(state, 0, None, Color::White);
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn path_from_root(&self) -> Vec<usize> {
+        let mut path: Vec<_> = self.ancestor_indices(self.current).collect();
+        path.reverse();
+        path
+    }
+
+    #[must_use]
+    pub fn path_to(&self, node: usize) -> Option<Vec<usize>> {
+        self.nodes.get(node)?;
+        let mut path: Vec<_> = self.ancestor_indices(node).collect();
+        path.reverse();
+        Some(path)
+    }
+
+    // Setup nodes such as handicap placement have no move and are skipped
+    #[must_use]
+    pub fn path_to_node(&self, target: usize) -> Option<Vec<Move>> {
+        self.nodes.get(target)?;
+        let mut moves: Vec<_> = self
+            .ancestors(target)
+            .filter_map(|node| node.mv.map(|(_, mv)| mv))
+            .collect();
+        moves.reverse();
+        Some(moves)
+    }
+
+    #[must_use]
+    pub fn half_move_number(&self) -> usize {
+        self.ancestors(self.current)
+            .filter(|node| node.mv.is_some())
+            .count()
+    }
+
+    #[must_use]
+    pub fn game_length(&self) -> usize {
+        self.half_move_number()
+    }
+
+    #[must_use]
+    pub fn to_play(&self) -> Color {
+        self.nodes[self.current].to_play
+    }
+
+    pub fn set_to_play(&mut self, color: Color) {
+        self.nodes[self.current].to_play = color;
+    }
+
+    /// Changes how `to_play` advances after each move. In `Alternating` mode,
+    /// both stone placements and passes hand the turn to the other player. In
+    /// `Black` or `White` mode, that color is to play and keeps playing,
+    /// including after a pass.
+    pub fn set_placement_mode(&mut self, mode: PlacementMode) {
+        self.placement_mode = mode;
+        match mode {
+            PlacementMode::Black => self.set_to_play(Color::Black),
+            PlacementMode::White => self.set_to_play(Color::White),
+            PlacementMode::Alternating => {}
+        }
+    }
+}
+
+// Cached hashes are left out since they only depend on the positions
+impl PartialEq for Tree {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+            && self.annotations == other.annotations
+            && self.current == other.current
+            && self.placement_mode == other.placement_mode
+            && self.ko_rule == other.ko_rule
+            && self.komi.to_bits() == other.komi.to_bits()
+    }
+}
+
+impl Eq for Tree {}
+
+impl Display for Tree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Move {} — {} to play (komi {})",
+            self.half_move_number(),
+            self.to_play(),
+            self.komi
+        )?;
+        write!(f, "{}", self.current())
+    }
 }
 
-#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum PlaceStoneError {
     #[error("The stone placement violates ko rules")]
     Ko,
     #[error("The stone placement results in self-capture")]
     SelfCapture,
-    #[error("Attempting to place a stone in an occupied intersection")]
+    #[error("The intersection is already occupied")]
     AlreadyExists,
+    #[error("An I/O error occurred")]
+    Io(#[from] std::io::Error),
 }
 
+// io::Error has no PartialEq, so I/O errors compare by kind
+impl PartialEq for PlaceStoneError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Io(a), Self::Io(b)) => a.kind() == b.kind(),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for PlaceStoneError {}
+
 #[cfg(test)]
 mod tests {
     use crate::mask::Mask;
@@ -126,7 +457,7 @@ mod tests {
 
     #[test]
     fn already_exists() {
-        let state = State::new(Mask::new([0b1]), Mask::EMPTY);
+        let state = State::from_masks(Mask::new([0b1]), Mask::EMPTY).unwrap();
         let mut tree = Tree::new(state, Color::White);
         assert_eq!(tree.place_stone(0, 0), Err(PlaceStoneError::AlreadyExists));
     }
@@ -139,7 +470,7 @@ mod tests {
             0b101,
             0b010,
         ]);
-        let state = State::new(Mask::EMPTY, white);
+        let state = State::from_masks(Mask::EMPTY, white).unwrap();
         let mut tree = Tree::new(state, Color::Black);
         assert_eq!(tree.place_stone(1, 1), Err(PlaceStoneError::SelfCapture));
     }
@@ -174,12 +505,51 @@ mod tests {
             0b0100,
         ]);
 
-        let expected = State::new(black_expected, white_expected);
-        let state = State::new(black, white);
+        let expected = State::from_masks(black_expected, white_expected).unwrap();
+        let state = State::from_masks(black, white).unwrap();
         let mut tree = Tree::new(state, Color::White);
         assert_eq!(tree.place_stone(1, 1), Ok(()));
         assert_eq!(tree.current(), &expected);
         assert_eq!(tree.place_stone(2, 1), Err(PlaceStoneError::Ko));
+        assert!(tree.is_ko_position(&state));
+        assert!(!tree.is_ko_position(&expected));
+    }
+
+    #[test]
+    fn superko() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b0010,
+            0b0101,
+            0b0010,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b0100,
+            0b1000,
+            0b0100,
+        ]);
+
+        let state = State::from_masks(black, white).unwrap();
+        let mut tree = Tree::new(state, Color::White);
+        tree.place_stone(1, 1).unwrap();
+        tree.pass();
+        tree.pass();
+
+        let mut simple = tree.clone();
+        assert!(!simple.is_ko_position(&state));
+        assert!(simple.legal_moves().contains(&Move::Place(2, 1)));
+        assert_eq!(simple.place_stone(2, 1), Ok(()));
+
+        assert!(simple.has_repeated_position(&state));
+        assert!(tree.has_repeated_position(&state));
+        assert!(!tree.has_repeated_position(&State::default()));
+
+        tree.ko_rule = KoRule::Superko;
+        assert!(tree.is_ko_position(&state));
+        assert!(!tree.legal_moves().contains(&Move::Place(2, 1)));
+        assert_eq!(tree.place_stone(2, 1), Err(PlaceStoneError::Ko));
     }
 
     #[test]
@@ -214,11 +584,15 @@ mod tests {
             0b000010000,
         ]);
 
-        let state = State::new(black, white);
-        let expected = State::new(black_expected, white_expected);
+        let state = State::from_masks(black, white).unwrap();
+        let expected = State::from_masks(black_expected, white_expected).unwrap();
         let mut tree = Tree::new(state, Color::Black);
+        assert_eq!(tree.last_capture_count(), 0);
         assert_eq!(tree.place_stone(4, 1), Ok(()));
         assert_eq!(tree.current(), &expected);
+        assert_eq!(tree.last_capture_count(), 6);
+        tree.pass();
+        assert_eq!(tree.last_capture_count(), 0);
     }
 
     #[test]
@@ -236,8 +610,8 @@ mod tests {
             0b0100,
         ]);
 
-        let expected = State::new(black_expected, Mask::EMPTY);
-        let state = State::new(black, white);
+        let expected = State::from_masks(black_expected, Mask::EMPTY).unwrap();
+        let state = State::from_masks(black, white).unwrap();
         let mut tree = Tree::new(state, Color::Black);
         assert_eq!(tree.place_stone(1, 0), Ok(()));
         assert_eq!(tree.current(), &expected);
@@ -257,10 +631,354 @@ mod tests {
             0b01,
         ]);
 
-        let expected = State::new(black_expected, Mask::EMPTY);
-        let state = State::new(black, white);
+        let expected = State::from_masks(black_expected, Mask::EMPTY).unwrap();
+        let state = State::from_masks(black, white).unwrap();
         let mut tree = Tree::new(state, Color::Black);
         assert_eq!(tree.place_stone(0, 1), Ok(()));
         assert_eq!(tree.current(), &expected);
     }
+
+    #[test]
+    fn replay() {
+        let tree = Tree::replay([Move::Place(0, 0), Move::Pass, Move::Place(1, 0)]).unwrap();
+
+        let mut expected = State::default();
+        expected.set(0, 0, Some(Color::Black));
+        expected.set(1, 0, Some(Color::Black));
+        assert_eq!(tree.current(), &expected);
+    }
+
+    #[test]
+    fn replay_reports_illegal_move() {
+        let moves = [Move::Place(0, 0), Move::Place(1, 0), Move::Place(0, 0)];
+        assert_eq!(
+            Tree::replay(moves),
+            Err((2, PlaceStoneError::AlreadyExists))
+        );
+    }
+
+    #[test]
+    fn from_state_and_moves() {
+        let state: State = [(3, 3, Color::Black), (15, 15, Color::White)]
+            .into_iter()
+            .collect();
+        let moves = [Move::Place(3, 15), Move::Pass, Move::Place(15, 3)];
+        let tree = Tree::from_state_and_moves(state, Color::White, &moves).unwrap();
+        assert_eq!(tree.node_count(), 4);
+        assert_eq!(tree.current().color_at(3, 3), Some(Color::Black));
+        assert_eq!(tree.current().color_at(3, 15), Some(Color::White));
+        assert_eq!(tree.current().color_at(15, 3), Some(Color::White));
+        assert_eq!(tree.to_play(), Color::Black);
+
+        let moves = [Move::Place(0, 0), Move::Place(3, 3)];
+        assert_eq!(
+            Tree::from_state_and_moves(state, Color::Black, &moves),
+            Err((1, PlaceStoneError::AlreadyExists))
+        );
+    }
+
+    #[test]
+    fn pass_keeps_position() {
+        let mut tree = Tree::empty();
+        tree.place_stone(3, 3).unwrap();
+        let before = *tree.current();
+        tree.pass();
+        assert_eq!(tree.current(), &before);
+        tree.place_stone(4, 4).unwrap();
+        assert_eq!(tree.current().color_at(4, 4), Some(Color::Black));
+    }
+
+    #[test]
+    fn from_state() {
+        let mut state = State::default();
+        state.set(3, 3, Some(Color::Black));
+        let mut tree = Tree::from_state(state, Color::White);
+        assert_eq!(tree.current(), &state);
+        tree.place_stone(4, 4).unwrap();
+        assert_eq!(tree.current().color_at(4, 4), Some(Color::White));
+    }
+
+    #[test]
+    fn legal_moves() {
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b010,
+            0b101,
+            0b010,
+        ]);
+        let mut state = State::from_masks(Mask::EMPTY, white).unwrap();
+        state.set(18, 18, Some(Color::Black));
+        let tree = Tree::new(state, Color::Black);
+
+        let moves = tree.legal_moves();
+        assert_eq!(moves.len(), 361 - 5 - 2);
+        assert!(!moves.contains(&Move::Place(0, 0)));
+        assert!(!moves.contains(&Move::Place(1, 1)));
+        assert!(!moves.contains(&Move::Place(18, 18)));
+        assert!(moves.contains(&Move::Place(3, 3)));
+    }
+
+    #[test]
+    fn node_count_and_capacity() {
+        let mut tree = Tree::with_capacity(100);
+        assert_eq!(tree.node_count(), 1);
+        let memory = tree.memory_usage_bytes();
+        assert!(memory >= 100 * std::mem::size_of::<Node>());
+
+        tree.place_stone(0, 0).unwrap();
+        tree.pass();
+        assert_eq!(tree.node_count(), 3);
+        assert_eq!(tree.memory_usage_bytes(), memory);
+
+        tree.annotate_current(NodeAnnotation {
+            comment: Some("x".repeat(10_000)),
+            evaluation: None,
+        });
+        assert!(tree.memory_usage_bytes() >= memory + 10_000);
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(
+            PlaceStoneError::AlreadyExists.to_string(),
+            "The intersection is already occupied"
+        );
+        assert_eq!(
+            PlaceStoneError::Ko.to_string(),
+            "The stone placement violates ko rules"
+        );
+    }
+
+    #[test]
+    fn error_source() {
+        use std::error::Error;
+
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "engine closed");
+        let error = PlaceStoneError::from(io);
+        let source = error.source().unwrap();
+        let inner = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(inner.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(inner.to_string(), "engine closed");
+
+        assert!(PlaceStoneError::Ko.source().is_none());
+        assert_ne!(error, PlaceStoneError::Ko);
+        assert_eq!(
+            error,
+            PlaceStoneError::Io(std::io::ErrorKind::UnexpectedEof.into())
+        );
+    }
+
+    #[test]
+    fn set_to_play() {
+        let mut tree = Tree::empty();
+        assert_eq!(tree.to_play(), Color::Black);
+        tree.set_to_play(Color::White);
+        tree.place_stone(0, 0).unwrap();
+        assert_eq!(tree.current().color_at(0, 0), Some(Color::White));
+        assert_eq!(tree.to_play(), Color::Black);
+
+        tree.placement_mode = PlacementMode::White;
+        tree.set_to_play(Color::Black);
+        tree.place_stone(1, 1).unwrap();
+        assert_eq!(tree.current().color_at(1, 1), Some(Color::Black));
+        assert_eq!(tree.to_play(), Color::Black);
+    }
+
+    #[test]
+    fn set_placement_mode() {
+        let mut tree = Tree::empty();
+        tree.set_placement_mode(PlacementMode::White);
+        assert_eq!(tree.to_play(), Color::White);
+        tree.pass();
+        assert_eq!(tree.to_play(), Color::White);
+        tree.place_stone(0, 0).unwrap();
+        assert_eq!(tree.to_play(), Color::White);
+
+        tree.set_placement_mode(PlacementMode::Alternating);
+        assert_eq!(tree.to_play(), Color::White);
+        tree.pass();
+        assert_eq!(tree.to_play(), Color::Black);
+        tree.place_stone(1, 1).unwrap();
+        assert_eq!(tree.to_play(), Color::White);
+
+        #[allow(deprecated)]
+        let toggle = PlacementMode::Toggle;
+        assert_eq!(toggle, PlacementMode::Alternating);
+    }
+
+    #[test]
+    fn to_play_after_move() {
+        let mut tree = Tree::empty();
+        assert_eq!(tree.to_play_after_move(&Move::Place(3, 3)), Color::White);
+        assert_eq!(tree.to_play_after_move(&Move::Pass), Color::White);
+
+        tree.set_placement_mode(PlacementMode::White);
+        assert_eq!(tree.to_play_after_move(&Move::Place(3, 3)), Color::White);
+        assert_eq!(tree.to_play_after_move(&Move::Pass), Color::White);
+
+        let mv = Move::Place(3, 3);
+        let expected = tree.to_play_after_move(&mv);
+        tree.apply(mv).unwrap();
+        assert_eq!(tree.to_play(), expected);
+    }
+
+    #[test]
+    fn place_handicap() {
+        let mut tree = Tree::empty();
+        assert_eq!(tree.place_handicap(4), Ok(()));
+        assert_eq!(tree.to_play(), Color::White);
+        assert_eq!(tree.current().black.count(), 4);
+        assert_eq!(tree.current().color_at(15, 3), Some(Color::Black));
+
+        assert_eq!(tree.place_handicap(2), Err(HandicapError::Occupied));
+        assert_eq!(
+            tree.place_handicap(12),
+            Err(HandicapError::InvalidCount(12))
+        );
+    }
+
+    #[test]
+    fn path_to_node() {
+        let mut tree = Tree::empty();
+        tree.place_handicap(2).unwrap();
+        let moves = [Move::Place(2, 2), Move::Pass, Move::Place(16, 16)];
+        tree.apply_all(moves).unwrap();
+
+        assert_eq!(tree.path_to_node(0), Some(vec![]));
+        assert_eq!(tree.path_to_node(1), Some(vec![]));
+        assert_eq!(tree.path_to_node(2), Some(vec![Move::Place(2, 2)]));
+        assert_eq!(tree.path_to_node(4), Some(moves.to_vec()));
+        assert_eq!(tree.path_to_node(5), None);
+    }
+
+    #[test]
+    fn go_to() {
+        let mut tree = Tree::replay([Move::Place(3, 3), Move::Place(15, 15)]).unwrap();
+        tree.go_to(1);
+        assert_eq!(tree.current().color_at(15, 15), None);
+        assert_eq!(tree.to_play(), Color::White);
+        tree.place_stone(15, 3).unwrap();
+        assert_eq!(tree.node_count(), 4);
+        assert_eq!(tree.half_move_number(), 2);
+        assert_eq!(
+            tree.path_to_node(3),
+            Some(vec![Move::Place(3, 3), Move::Place(15, 3)])
+        );
+    }
+
+    #[test]
+    fn tree_equality() {
+        fn assert_eq_impl<T: Eq>(_: &T) {}
+
+        let mut tree = Tree::replay([Move::Place(3, 3)]).unwrap();
+        tree.komi = f32::NAN;
+        tree.annotate_current(NodeAnnotation {
+            comment: None,
+            evaluation: Some(f32::NAN),
+        });
+        assert_eq_impl(&tree);
+        assert_eq!(tree, tree.clone());
+
+        let mut other = tree.clone();
+        other.komi = 6.5;
+        assert_ne!(tree, other);
+        other = tree.clone();
+        other.go_to(0);
+        assert_ne!(tree, other);
+    }
+
+    #[test]
+    fn go_to_restores_to_play() {
+        let mut tree = Tree::replay([Move::Place(3, 3)]).unwrap();
+        tree.go_to(0);
+        assert_eq!(tree.to_play(), Color::Black);
+        tree.go_to(1);
+        assert_eq!(tree.to_play(), Color::White);
+
+        let mut tree = Tree::empty();
+        tree.place_handicap(4).unwrap();
+        tree.place_stone(9, 9).unwrap();
+        tree.go_to(1);
+        assert_eq!(tree.to_play(), Color::White);
+        tree.go_to(0);
+        assert_eq!(tree.to_play(), Color::Black);
+    }
+
+    #[test]
+    fn stones_ever_placed_by() {
+        // White's stone at (0, 0) is captured by the last move
+        let tree = Tree::replay([
+            Move::Place(1, 0),
+            Move::Place(0, 0),
+            Move::Pass,
+            Move::Place(5, 5),
+            Move::Place(0, 1),
+        ])
+        .unwrap();
+        assert_eq!(tree.current().color_at(0, 0), None);
+
+        let white: Mask = [(0, 0), (5, 5)].into_iter().collect();
+        let black: Mask = [(1, 0), (0, 1)].into_iter().collect();
+        assert_eq!(tree.stones_ever_placed_by(Color::White), white);
+        assert_eq!(tree.stones_ever_placed_by(Color::Black), black);
+    }
+
+    #[test]
+    fn path_from_root() {
+        let mut tree = Tree::replay([Move::Place(3, 3), Move::Place(15, 15)]).unwrap();
+        assert_eq!(tree.path_from_root(), vec![0, 1, 2]);
+        tree.go_to(1);
+        tree.pass();
+        assert_eq!(tree.path_from_root(), vec![0, 1, 3]);
+        assert_eq!(tree.path_to(2), Some(vec![0, 1, 2]));
+        assert_eq!(tree.path_to(0), Some(vec![0]));
+        assert_eq!(tree.path_to(4), None);
+    }
+
+    #[test]
+    fn half_move_number() {
+        let mut tree = Tree::empty();
+        assert_eq!(tree.half_move_number(), 0);
+        tree.place_stone(3, 3).unwrap();
+        tree.pass();
+        tree.place_stone(4, 4).unwrap();
+        assert_eq!(tree.half_move_number(), 3);
+        assert_eq!(tree.game_length(), 3);
+
+        // Handicap stones are set up, not played
+        let mut tree = Tree::empty();
+        tree.place_handicap(2).unwrap();
+        assert_eq!(tree.half_move_number(), 0);
+        assert!(tree.to_string().starts_with("Move 0 — White to play"));
+        tree.place_stone(9, 9).unwrap();
+        assert_eq!(tree.half_move_number(), 1);
+    }
+
+    #[test]
+    fn display() {
+        let mut tree = Tree::empty();
+        tree.komi = 6.5;
+        tree.place_stone(3, 15).unwrap();
+        let display = tree.to_string();
+        let lines: Vec<_> = display.lines().collect();
+        assert_eq!(lines[0], "Move 1 — White to play (komi 6.5)");
+        assert_eq!(lines[17], " 4 . . . X . . . . . . . . . . . . . . .");
+    }
+
+    #[test]
+    fn annotations() {
+        let mut tree = Tree::empty();
+        tree.place_stone(3, 3).unwrap();
+        let annotation = NodeAnnotation {
+            comment: Some("Star point".to_string()),
+            evaluation: Some(0.5),
+        };
+        tree.annotate_current(annotation.clone());
+        tree.pass();
+
+        assert_eq!(tree.annotation_at(0), None);
+        assert_eq!(tree.annotation_at(1), Some(&annotation));
+        assert_eq!(tree.annotation_at(2), None);
+        assert_eq!(tree.annotation_at(3), None);
+    }
 }
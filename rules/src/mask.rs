@@ -1,29 +1,141 @@
 use std::{
+    cmp::Reverse,
     fmt::{self, Debug, Formatter},
-    ops::{Deref, DerefMut, Index, IndexMut, Not},
+    iter::Rev,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Deref, DerefMut, Index,
+        IndexMut, Not,
+    },
 };
 
-use crate::mask_row::MaskRow;
+use crate::{
+    fen::{self, FenError},
+    mask_row::MaskRow,
+};
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[repr(transparent)]
 pub struct Mask([MaskRow; 19]);
 
 impl Mask {
     pub const EMPTY: Self = Self([MaskRow::EMPTY; 19]);
     pub const FILLED: Self = Self([MaskRow::FILLED; 19]);
 
-    pub fn new<const N: usize>(rows: [u32; N]) -> Self {
+    pub const BORDER: Self = Self::new(edge_rows(0b1111111111111111111, 0b1000000000000000001));
+    pub const INTERIOR: Self = Self::new(edge_rows(0, 0b0111111111111111110));
+
+    // Row 0 is the top of the board, so A19 is (0, 0) and T1 is (18, 18)
+
+    /// The main diagonal, where `x == y`, from A19 to T1.
+    pub const DIAGONAL_A19_T1: Self = Self::diagonal(0);
+    /// The anti-diagonal, where `x + y == 18`, from A1 to T19.
+    pub const DIAGONAL_A1_T19: Self = Self::new(anti_diagonal_rows());
+
+    #[must_use]
+    pub const fn new<const N: usize>(rows: [u32; N]) -> Self {
         assert!(
             N <= 19,
             "Cannot initialize a mask with more than nineteen rows"
         );
-        let mut mask = Self::default();
-        for (i, row) in rows.into_iter().enumerate() {
-            mask.0[i] = MaskRow::new(row);
+        let mut mask = Self::EMPTY;
+        let mut i = 0;
+        while i < N {
+            mask.0[i] = MaskRow::new(rows[i]);
+            i += 1;
         }
         mask
     }
 
+    /// The intersections where `x - y == offset`. Offsets beyond ±18 give an
+    /// empty mask.
+    #[must_use]
+    pub const fn diagonal(offset: isize) -> Self {
+        let mut rows = [0; 19];
+        let mut y = 0;
+        while y < 19 {
+            let x = y as isize + offset;
+            if x >= 0 && x <= 18 {
+                rows[y] = 1 << x;
+            }
+            y += 1;
+        }
+        Self::new(rows)
+    }
+
+    /// Low level: builds a mask from raw rows, where bit `x` of row `y` is
+    /// the intersection at `(x, y)`. Panics if any row has bits above 18.
+    #[must_use]
+    pub fn from_u32_array(rows: [u32; 19]) -> Self {
+        Self::new(rows)
+    }
+
+    /// Low level: copies out the raw rows, in the layout accepted by
+    /// [`Mask::from_u32_array`].
+    #[must_use]
+    pub fn to_u32_array(&self) -> [u32; 19] {
+        self.0.map(|row| *row)
+    }
+
+    /// Low level: borrows the raw rows without copying.
+    #[must_use]
+    pub fn as_u32_slice(&self) -> &[u32] {
+        // SAFETY: Mask and MaskRow are both repr(transparent), so the rows
+        // have the same layout as [u32; 19].
+        unsafe { std::slice::from_raw_parts(self.0.as_ptr().cast::<u32>(), 19) }
+    }
+
+    /// Encodes each row as digits for runs of empty intersections and `b`
+    /// for set ones, matching a `State` with only black stones.
+    #[must_use]
+    pub fn to_fen(&self) -> String {
+        fen::encode(|x, y| self.get(x, y).then_some('b'))
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut mask = Self::EMPTY;
+        fen::decode(fen, |x, y, c| match c {
+            'b' => {
+                mask.set(x, y);
+                Ok(())
+            }
+            _ => Err(FenError::InvalidCharacter(c)),
+        })?;
+        Ok(mask)
+    }
+
+    #[must_use]
+    pub fn to_u64_array(&self) -> [u64; 6] {
+        let mut words = [0u64; 6];
+        for (y, row) in self.row_iter() {
+            let offset = y * 19;
+            let (word, shift) = (offset / 64, offset % 64);
+            words[word] |= (**row as u64) << shift;
+            if shift + 19 > 64 {
+                words[word + 1] |= (**row as u64) >> (64 - shift);
+            }
+        }
+        words
+    }
+
+    pub fn from_u64_array(words: [u64; 6]) -> Result<Self, MaskDecodeError> {
+        if words[5] >> (361 - 320) != 0 {
+            return Err(MaskDecodeError::OutOfBounds);
+        }
+        let mut mask = Self::EMPTY;
+        for (y, row) in mask.row_iter_mut() {
+            let offset = y * 19;
+            let (word, shift) = (offset / 64, offset % 64);
+            let mut bits = words[word] >> shift;
+            if shift + 19 > 64 {
+                bits |= words[word + 1] << (64 - shift);
+            }
+            *row = MaskRow::new(bits as u32 & *MaskRow::FILLED);
+        }
+        Ok(mask)
+    }
+
+    #[must_use]
     pub fn get(&self, x: usize, y: usize) -> bool {
         assert!(x <= 18);
         assert!(y <= 18);
@@ -36,21 +148,142 @@ impl Mask {
         self[y].set(x);
     }
 
+    #[must_use]
+    pub fn get_row(&self, y: usize) -> MaskRow {
+        assert!(y <= 18);
+        self[y]
+    }
+
+    pub fn set_row(&mut self, y: usize, row: MaskRow) {
+        assert!(y <= 18);
+        self[y] = row;
+    }
+
+    #[must_use]
+    pub fn column(&self, x: usize) -> u32 {
+        assert!(x <= 18);
+        self.row_iter()
+            .fold(0, |column, (y, row)| column | u32::from(row.get(x)) << y)
+    }
+
+    pub fn set_column(&mut self, x: usize, column: u32) {
+        assert!(x <= 18);
+        assert!(
+            column & !*MaskRow::FILLED == 0,
+            "Cannot have a column with more than 19 stones"
+        );
+        for (y, row) in self.row_iter_mut() {
+            if column >> y & 1 == 1 {
+                row.set(x);
+            } else {
+                row.unset(x);
+            }
+        }
+    }
+
     pub fn unset(&mut self, x: usize, y: usize) {
         assert!(x <= 18);
         assert!(y <= 18);
         self[y].unset(x)
     }
 
+    pub fn toggle(&mut self, x: usize, y: usize) {
+        assert!(x <= 18);
+        assert!(y <= 18);
+        self[y].toggle(x)
+    }
+
     pub fn expand_once(&mut self, stencil: &Mask) {
-        let mut prev = self[0];
-        self[0] = (self[1] | self[0].expand()) & stencil[0];
+        let source = *self;
+        source.expand_into(stencil, self);
+    }
+
+    #[inline]
+    pub fn expand_unconstrained(&mut self) {
+        self.expand_once(&Mask::FILLED);
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn expanded_unconstrained(&self) -> Self {
+        let mut mask = *self;
+        mask.expand_unconstrained();
+        mask
+    }
+
+    pub fn shrink(&mut self) {
+        // Off-board neighbours count as unset, so edge points never survive
+        *self &= !(!*self).expanded_unconstrained() & Mask::INTERIOR;
+    }
+
+    #[must_use]
+    pub fn shrunk(&self) -> Self {
+        let mut mask = *self;
+        mask.shrink();
+        mask
+    }
+
+    #[must_use]
+    pub fn has_interior(&self) -> bool {
+        !self.shrunk().is_empty()
+    }
+
+    #[must_use]
+    pub fn interior_count(&self) -> u32 {
+        self.shrunk().count()
+    }
+
+    #[must_use]
+    pub fn within_manhattan_distance(x: usize, y: usize, d: usize) -> Self {
+        let mut mask = Mask::EMPTY;
+        mask.set(x, y);
+        mask.expanded_by_distance(d)
+    }
+
+    #[must_use]
+    pub fn expanded_by_distance(&self, d: usize) -> Self {
+        let mut mask = *self;
+        // Any distance of 36 or more covers the whole board
+        for _ in 0..d.min(36) {
+            mask.expand_unconstrained();
+        }
+        mask
+    }
+
+    pub fn expand_diagonal_once(&mut self, stencil: &Mask) {
+        let source = *self;
+        for y in 0..19 {
+            let mut row = source[y];
+            if y > 0 {
+                row |= source[y - 1] << 1 | source[y - 1] >> 1;
+            }
+            if y < 18 {
+                row |= source[y + 1] << 1 | source[y + 1] >> 1;
+            }
+            self[y] = row & stencil[y];
+        }
+    }
+
+    pub fn expand_8connected_once(&mut self, stencil: &Mask) {
+        let source = *self;
+        for y in 0..19 {
+            let mut row = source[y];
+            if y > 0 {
+                row |= source[y - 1];
+            }
+            if y < 18 {
+                row |= source[y + 1];
+            }
+            self[y] = row.expand() & stencil[y];
+        }
+    }
+
+    pub fn expand_into(&self, stencil: &Mask, target: &mut Mask) {
+        target[0] = (self[1] | self[0].expand()) & stencil[0];
         for i in 1..=17 {
-            let temp = self[i];
-            self[i] = (prev | self[i].expand() | self[i + 1]) & stencil[i];
-            prev = temp;
+            target[i] = (self[i - 1] | self[i].expand() | self[i + 1]) & stencil[i];
         }
-        self[18] = (prev | self[18].expand()) & stencil[18];
+        target[18] = (self[17] | self[18].expand()) & stencil[18];
     }
 
     pub fn expand_all(&mut self, stencil: &Mask) {
@@ -63,13 +296,131 @@ impl Mask {
         }
     }
 
+    pub fn expand_all_with_limit(&mut self, stencil: &Mask, max_steps: usize) -> usize {
+        for step in 0..max_steps {
+            let prev = *self;
+            self.expand_once(stencil);
+            if self == &prev {
+                return step;
+            }
+        }
+        max_steps
+    }
+
+    #[must_use]
     pub fn flood(&self, x: usize, y: usize) -> Self {
-        let mut mask = Mask::EMPTY;
-        mask.set(x, y);
+        let mut seed = Mask::EMPTY;
+        seed.set(x, y);
+        self.flood_from_mask(&seed)
+    }
+
+    #[must_use]
+    pub fn flood_from_mask(&self, seeds: &Mask) -> Self {
+        let mut mask = seeds & self;
         mask.expand_all(self);
         mask
     }
 
+    #[must_use]
+    pub fn rotate_90_cw(&self) -> Self {
+        self.transpose().mirror_x()
+    }
+
+    #[must_use]
+    pub fn rotate_90_ccw(&self) -> Self {
+        self.transpose().mirror_y()
+    }
+
+    #[must_use]
+    pub fn rotate_180(&self) -> Self {
+        self.mirror_x().mirror_y()
+    }
+
+    #[must_use]
+    pub fn mirror_x(&self) -> Self {
+        let mut mask = *self;
+        for row in mask.rows_mut() {
+            *row = MaskRow::new(row.reverse_bits() >> 13);
+        }
+        mask
+    }
+
+    #[must_use]
+    pub fn mirror_y(&self) -> Self {
+        let mut mask = *self;
+        mask.0.reverse();
+        mask
+    }
+
+    #[must_use]
+    pub fn transpose(&self) -> Self {
+        self.iter_positions().map(|(x, y)| (y, x)).collect()
+    }
+
+    #[must_use]
+    pub fn anti_transpose(&self) -> Self {
+        self.iter_positions()
+            .map(|(x, y)| (18 - y, 18 - x))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn connected_components(&self) -> Vec<Mask> {
+        let mut components = vec![];
+        self.for_each_component(|component| components.push(component));
+        components
+    }
+
+    #[must_use]
+    pub fn component_count(&self) -> usize {
+        let mut count = 0;
+        self.for_each_component(|_| count += 1);
+        count
+    }
+
+    #[must_use]
+    pub fn largest_component(&self) -> Option<Mask> {
+        self.connected_components()
+            .into_iter()
+            .max_by_key(|component| (component.count(), Reverse(component.seed())))
+    }
+
+    #[must_use]
+    pub fn smallest_component(&self) -> Option<Mask> {
+        self.connected_components()
+            .into_iter()
+            .min_by_key(|component| (component.count(), component.seed()))
+    }
+
+    // The lexicographically smallest (x, y) position, used for tie-breaking
+    fn seed(&self) -> Option<(usize, usize)> {
+        self.iter_positions().min()
+    }
+
+    fn for_each_component(&self, mut f: impl FnMut(Mask)) {
+        let mut remaining = *self;
+        for y in 0..19 {
+            while *remaining[y] != 0 {
+                let x = remaining[y].trailing_zeros() as usize;
+                let component = remaining.flood(x, y);
+                remaining &= !component;
+                f(component);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn neighbor_count_mask(&self) -> [[u8; 19]; 19] {
+        let mut counts = [[0; 19]; 19];
+        for (y, row) in counts.iter_mut().enumerate() {
+            for (x, count) in row.iter_mut().enumerate() {
+                *count = neighbors(x, y).filter(|&(x, y)| self.get(x, y)).count() as u8;
+            }
+        }
+        counts
+    }
+
+    #[must_use]
     pub fn has_a_liberty(&self, opponent: &Mask) -> bool {
         if *((self[1] | self[0].expand()) & !self[0] & !opponent[0]) > 0 {
             return true;
@@ -82,6 +433,205 @@ impl Mask {
         *((self[17] | self[18].expand()) & !self[18] & !opponent[18]) > 0
     }
 
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        !self.any_row_nonzero()
+    }
+
+    pub fn fold_rows<T, F: Fn(T, &MaskRow) -> T>(&self, init: T, f: F) -> T {
+        self.rows().fold(init, f)
+    }
+
+    #[must_use]
+    pub fn row_union(&self) -> MaskRow {
+        self.fold_rows(MaskRow::EMPTY, |acc, &row| acc | row)
+    }
+
+    #[must_use]
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let y_min = self.0.iter().position(|row| !row.is_empty())?;
+        let y_max = self.0.iter().rposition(|row| !row.is_empty())?;
+        let columns = self.row_union();
+        let x_min = columns.trailing_zeros() as usize;
+        let x_max = 31 - columns.leading_zeros() as usize;
+        Some((x_min, y_min, x_max, y_max))
+    }
+
+    #[must_use]
+    pub fn row_intersection(&self) -> MaskRow {
+        self.fold_rows(MaskRow::FILLED, |acc, &row| acc & row)
+    }
+
+    #[must_use]
+    pub fn any_row_nonzero(&self) -> bool {
+        self.rows().any(|row| **row != 0)
+    }
+
+    #[must_use]
+    pub fn all_rows_match(&self, pred: impl Fn(&MaskRow) -> bool) -> bool {
+        self.rows().all(pred)
+    }
+
+    #[must_use]
+    pub fn is_subset_of(&self, other: &Mask) -> bool {
+        self.rows()
+            .zip(other.rows())
+            .all(|(&row, &other)| row & other == row)
+    }
+
+    #[must_use]
+    pub fn is_superset_of(&self, other: &Mask) -> bool {
+        other.is_subset_of(self)
+    }
+
+    #[must_use]
+    pub fn is_disjoint_from(&self, other: &Mask) -> bool {
+        self.rows()
+            .zip(other.rows())
+            .all(|(&row, &other)| *(row & other) == 0)
+    }
+
+    #[must_use]
+    pub fn count(&self) -> u32 {
+        self.rows().map(|row| row.count_ones()).sum()
+    }
+
+    #[must_use]
+    pub fn popcount_per_row(&self) -> [u32; 19] {
+        self.0.map(|row| row.count_ones())
+    }
+
+    #[must_use]
+    #[deprecated(note = "Renamed to `Mask::popcount_per_row`")]
+    pub fn count_per_row(&self) -> [u32; 19] {
+        self.popcount_per_row()
+    }
+
+    #[must_use]
+    #[deprecated(note = "Renamed to `Mask::popcount_per_column`")]
+    pub fn count_per_column(&self) -> [u32; 19] {
+        self.popcount_per_column()
+    }
+
+    #[must_use]
+    pub fn popcount_per_column(&self) -> [u32; 19] {
+        let mut counts = [0; 19];
+        for row in self.rows() {
+            for (x, count) in counts.iter_mut().enumerate() {
+                *count += u32::from(row.get(x));
+            }
+        }
+        counts
+    }
+
+    #[must_use]
+    pub fn center_of_mass(&self) -> Option<(f32, f32)> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let (x_sum, y_sum) = self
+            .iter_positions()
+            .fold((0, 0), |(x_sum, y_sum), (x, y)| (x_sum + x, y_sum + y));
+        let count = count as f32;
+        Some((x_sum as f32 / count, y_sum as f32 / count))
+    }
+
+    #[must_use]
+    pub fn centroid(&self) -> Option<(usize, usize)> {
+        let (x, y) = self.center_of_mass()?;
+        Some((x.round() as usize, y.round() as usize))
+    }
+
+    #[must_use]
+    pub fn density(&self) -> f32 {
+        self.count() as f32 / 361.0
+    }
+
+    #[must_use]
+    pub fn density_in_region(&self, region: &Mask) -> f32 {
+        let size = region.count();
+        if size == 0 {
+            return 0.0;
+        }
+        self.intersection_count(region) as f32 / size as f32
+    }
+
+    /// Intersections set in both masks. For example, the liberties of a group
+    /// are the empty points in its outline:
+    ///
+    /// ```
+    /// # use go_rules::Mask;
+    /// let group = Mask::new([0b00, 0b10]);
+    /// let occupied = Mask::new([0b10, 0b10]);
+    /// let liberties = group.outline().intersection(&!occupied);
+    /// assert_eq!(liberties.count(), 3);
+    /// ```
+    #[must_use]
+    pub fn intersection(&self, other: &Mask) -> Mask {
+        self & other
+    }
+
+    /// Intersections set in either mask. For example, joining two groups
+    /// connected by a newly placed stone:
+    ///
+    /// ```
+    /// # use go_rules::Mask;
+    /// let left = Mask::new([0b0011]);
+    /// let right = Mask::new([0b1000]);
+    /// let stone = Mask::new([0b0100]);
+    /// let joined = left.union(&right).union(&stone);
+    /// assert_eq!(joined.connected_components().len(), 1);
+    /// ```
+    #[must_use]
+    pub fn union(&self, other: &Mask) -> Mask {
+        self | other
+    }
+
+    #[must_use]
+    pub fn intersection_count(&self, other: &Mask) -> u32 {
+        self.rows()
+            .zip(other.rows())
+            .map(|(&row, &other)| (row & other).count_ones())
+            .sum()
+    }
+
+    #[must_use]
+    pub fn union_count(&self, other: &Mask) -> u32 {
+        self.rows()
+            .zip(other.rows())
+            .map(|(&row, &other)| (row | other).count_ones())
+            .sum()
+    }
+
+    #[must_use]
+    pub fn hamming_distance(&self, other: &Mask) -> u32 {
+        (self ^ other).count()
+    }
+
+    #[must_use]
+    pub fn outline(&self) -> Self {
+        let mut outline = self.expanded_unconstrained();
+        for (row, &mask) in outline.rows_mut().zip(self.rows()) {
+            *row &= !mask;
+        }
+        outline
+    }
+
+    #[must_use]
+    pub fn boundary_between(a: &Mask, b: &Mask) -> Mask {
+        a & b.expanded_unconstrained()
+    }
+
+    #[must_use]
+    pub fn perimeter(&self, border: &Mask) -> u32 {
+        self.outline()
+            .rows()
+            .zip(border.rows())
+            .map(|(&outline, &border)| (outline & !border).count_ones())
+            .sum()
+    }
+
     pub fn rows(&self) -> impl Iterator<Item = &MaskRow> {
         self.0.iter()
     }
@@ -89,6 +639,124 @@ impl Mask {
     pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut MaskRow> {
         self.0.iter_mut()
     }
+
+    pub fn row_iter(&self) -> impl Iterator<Item = (usize, &MaskRow)> {
+        self.rows().enumerate()
+    }
+
+    pub fn row_iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut MaskRow)> {
+        self.rows_mut().enumerate()
+    }
+
+    pub fn iter_positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.bit_scan_forward()
+    }
+
+    #[must_use]
+    pub fn bit_scan_forward(&self) -> MaskBitScanIter {
+        MaskBitScanIter::new(*self)
+    }
+
+    #[must_use = "iterators are lazy and do nothing unless consumed"]
+    pub fn bit_scan_reverse(&self) -> Rev<MaskBitScanIter> {
+        MaskBitScanIter::new(*self).rev()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaskBitScanIter {
+    remaining: Mask,
+    front: usize,
+    back: usize,
+}
+
+impl MaskBitScanIter {
+    fn new(mask: Mask) -> Self {
+        Self {
+            remaining: mask,
+            front: 0,
+            back: 19,
+        }
+    }
+}
+
+impl Iterator for MaskBitScanIter {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            let row = &mut self.remaining[self.front];
+            if !row.is_empty() {
+                let x = row.trailing_zeros() as usize;
+                row.unset(x);
+                return Some((x, self.front));
+            }
+            self.front += 1;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self
+            .remaining
+            .rows()
+            .take(self.back)
+            .skip(self.front)
+            .map(|row| row.count_ones() as usize)
+            .sum();
+        (count, Some(count))
+    }
+}
+
+impl DoubleEndedIterator for MaskBitScanIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            let row = &mut self.remaining[self.back - 1];
+            if !row.is_empty() {
+                let x = 31 - row.leading_zeros() as usize;
+                row.unset(x);
+                return Some((x, self.back - 1));
+            }
+            self.back -= 1;
+        }
+        None
+    }
+}
+
+impl ExactSizeIterator for MaskBitScanIter {}
+
+pub(crate) fn neighbors(x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    [
+        (x > 0).then(|| (x - 1, y)),
+        (x < 18).then(|| (x + 1, y)),
+        (y > 0).then(|| (x, y - 1)),
+        (y < 18).then(|| (x, y + 1)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum MaskDecodeError {
+    #[error("Bits beyond the 361 board intersections are set")]
+    OutOfBounds,
+}
+
+const fn anti_diagonal_rows() -> [u32; 19] {
+    let mut rows = [0; 19];
+    let mut y = 0;
+    while y < 19 {
+        rows[y] = 1 << (18 - y);
+        y += 1;
+    }
+    rows
+}
+
+const fn edge_rows(edge: u32, middle: u32) -> [u32; 19] {
+    let mut rows = [middle; 19];
+    rows[0] = edge;
+    rows[18] = edge;
+    rows
 }
 
 impl Deref for Mask {
@@ -108,64 +776,120 @@ impl DerefMut for Mask {
 impl Not for Mask {
     type Output = Mask;
 
+    // Each row is complemented within the board, so bits 19-31 stay clear
     fn not(self) -> Self::Output {
-        Self([
-            !self[0],
-            !self[1],
-            !self[2],
-            !self[3],
-            !self[4],
-            !self[5],
-            !self[6],
-            !self[7],
-            !self[8],
-            !self[9],
-            !self[10],
-            !self[11],
-            !self[12],
-            !self[13],
-            !self[14],
-            !self[15],
-            !self[16],
-            !self[17],
-            !self[18],
-        ])
+        Self(self.0.map(Not::not))
     }
 }
 
-impl Index<usize> for Mask {
-    type Output = MaskRow;
+impl Not for &Mask {
+    type Output = Mask;
 
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.0[index]
+    fn not(self) -> Self::Output {
+        !*self
     }
 }
 
-impl IndexMut<usize> for Mask {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.0[index]
-    }
-}
+// The operator traits already mark their methods `#[must_use]`, so discarding
+// `a & b` where `a &= b` was meant is warned about without extra attributes.
+macro_rules! impl_binary_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident) => {
+        impl $op_assign for Mask {
+            fn $method_assign(&mut self, rhs: Mask) {
+                for (row, &other) in self.rows_mut().zip(rhs.rows()) {
+                    row.$method_assign(other);
+                }
+            }
+        }
 
-impl Debug for Mask {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for row in self.rows() {
-            write!(f, "{:?}\n", row)?;
+        impl $op_assign<&Mask> for Mask {
+            fn $method_assign(&mut self, rhs: &Mask) {
+                self.$method_assign(*rhs);
+            }
         }
-        Ok(())
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        impl $op for Mask {
+            type Output = Mask;
 
-    #[test]
-    fn debug_format() {
-        // Right part
-        #[rustfmt::skip]
-        let mut mask = Mask::new([
-            0b0000, 
+            fn $method(mut self, rhs: Mask) -> Self::Output {
+                self.$method_assign(rhs);
+                self
+            }
+        }
+
+        impl $op<&Mask> for Mask {
+            type Output = Mask;
+
+            fn $method(self, rhs: &Mask) -> Self::Output {
+                self.$method(*rhs)
+            }
+        }
+
+        impl $op<Mask> for &Mask {
+            type Output = Mask;
+
+            fn $method(self, rhs: Mask) -> Self::Output {
+                (*self).$method(rhs)
+            }
+        }
+
+        impl $op<&Mask> for &Mask {
+            type Output = Mask;
+
+            fn $method(self, rhs: &Mask) -> Self::Output {
+                (*self).$method(*rhs)
+            }
+        }
+    };
+}
+
+impl_binary_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_binary_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_binary_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+impl FromIterator<(usize, usize)> for Mask {
+    fn from_iter<T: IntoIterator<Item = (usize, usize)>>(iter: T) -> Self {
+        let mut mask = Self::EMPTY;
+        for (x, y) in iter {
+            mask.set(x, y);
+        }
+        mask
+    }
+}
+
+impl Index<usize> for Mask {
+    type Output = MaskRow;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Mask {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl Debug for Mask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            writeln!(f, "{:?}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_format() {
+        // Right part
+        #[rustfmt::skip]
+        let mut mask = Mask::new([
+            0b0000, 
             0b0100, 
             0b1110, 
             0b0100,
@@ -302,6 +1026,83 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn expand_diagonal() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b000,
+            0b010,
+        ]);
+
+        #[rustfmt::skip]
+        let diagonal = Mask::new([
+            0b101,
+            0b010,
+            0b101,
+        ]);
+
+        #[rustfmt::skip]
+        let connected = Mask::new([
+            0b111,
+            0b111,
+            0b111,
+        ]);
+
+        let mut expanded = mask;
+        expanded.expand_diagonal_once(&Mask::FILLED);
+        assert_eq!(expanded, diagonal);
+
+        let mut expanded = mask;
+        expanded.expand_8connected_once(&Mask::FILLED);
+        assert_eq!(expanded, connected);
+
+        let mut expanded = mask;
+        expanded.expand_8connected_once(&Mask::new([0b001, 0b011]));
+        assert_eq!(expanded, Mask::new([0b001, 0b011]));
+
+        let mut corner = Mask::EMPTY;
+        corner.set(18, 18);
+        corner.expand_diagonal_once(&Mask::FILLED);
+        assert_eq!(corner.count(), 2);
+        assert!(corner.get(17, 17));
+    }
+
+    #[test]
+    fn expand_all_with_limit() {
+        let stencil = Mask::new([0b11111]);
+        let mut mask = Mask::new([0b00001]);
+        assert_eq!(mask.expand_all_with_limit(&stencil, 2), 2);
+        assert_eq!(mask, Mask::new([0b00111]));
+        assert_eq!(mask.expand_all_with_limit(&stencil, 10), 2);
+        assert_eq!(mask, stencil);
+        assert_eq!(mask.expand_all_with_limit(&stencil, 10), 0);
+    }
+
+    #[test]
+    fn flood_from_mask() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b11001,
+            0b00001,
+            0b01111,
+            0b00000,
+            0b11000,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b11001,
+            0b00001,
+            0b01111,
+        ]);
+
+        let seeds = Mask::new([0b10001]);
+        assert_eq!(mask.flood_from_mask(&seeds), expected);
+        // Seeds outside the region do not flood anything
+        assert!(mask.flood_from_mask(&Mask::new([0, 0b10])).is_empty());
+        assert_eq!(mask.flood_from_mask(&Mask::FILLED), mask);
+    }
+
     #[test]
     fn flood() {
         #[rustfmt::skip]
@@ -323,4 +1124,803 @@ mod tests {
 
         assert_eq!(a_group, expected);
     }
+
+    #[test]
+    fn count() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b01000,
+            0b11100,
+            0b01010,
+        ]);
+        assert_eq!(mask.count(), 6);
+        assert_eq!(Mask::EMPTY.count(), 0);
+        assert_eq!(Mask::FILLED.count(), 361);
+    }
+
+    #[test]
+    fn iter_positions() {
+        let mask: Mask = [(3, 0), (1, 2), (0, 2), (18, 18)].into_iter().collect();
+        let positions: Vec<_> = mask.iter_positions().collect();
+        assert_eq!(positions, vec![(3, 0), (0, 2), (1, 2), (18, 18)]);
+        assert_eq!(Mask::EMPTY.iter_positions().next(), None);
+        assert_eq!(Mask::FILLED.iter_positions().count(), 361);
+    }
+
+    #[test]
+    fn bit_scan() {
+        let mask: Mask = [(3, 0), (18, 0), (0, 2), (1, 2), (18, 18)]
+            .into_iter()
+            .collect();
+        let forward: Vec<_> = mask.bit_scan_forward().collect();
+        assert_eq!(forward, vec![(3, 0), (18, 0), (0, 2), (1, 2), (18, 18)]);
+        let reverse: Vec<_> = mask.bit_scan_reverse().collect();
+        assert_eq!(reverse, vec![(18, 18), (1, 2), (0, 2), (18, 0), (3, 0)]);
+
+        let mut iter = mask.bit_scan_forward();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some((3, 0)));
+        assert_eq!(iter.next_back(), Some((18, 18)));
+        assert_eq!(iter.next_back(), Some((1, 2)));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some((18, 0)));
+        assert_eq!(iter.next(), Some((0, 2)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(Mask::EMPTY.bit_scan_forward().next(), None);
+        assert_eq!(Mask::FILLED.bit_scan_reverse().count(), 361);
+    }
+
+    #[test]
+    fn shrink() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b00000,
+            0b01110,
+            0b01110,
+            0b01110,
+        ]);
+        let mut expected = Mask::EMPTY;
+        expected.set(2, 2);
+        assert_eq!(mask.shrunk(), expected);
+        assert!(mask.has_interior());
+        assert_eq!(mask.interior_count(), 1);
+
+        let mut shrunk = mask;
+        shrunk.shrink();
+        shrunk.shrink();
+        assert!(shrunk.is_empty());
+
+        // Edge points have no off-board neighbours to surround them
+        assert_eq!(Mask::FILLED.shrunk(), Mask::INTERIOR);
+        assert_eq!(Mask::FILLED.interior_count(), 17 * 17);
+        assert!(!Mask::BORDER.has_interior());
+        assert!(!Mask::EMPTY.has_interior());
+    }
+
+    #[test]
+    fn expanded_by_distance() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b00000,
+            0b00000,
+            0b00110,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b00110,
+            0b01111,
+            0b11111,
+            0b01111,
+            0b00110,
+        ]);
+
+        assert_eq!(mask.expanded_by_distance(0), mask);
+        assert_eq!(mask.expanded_by_distance(2), expected);
+        assert_eq!(mask.expanded_by_distance(40), Mask::FILLED);
+        assert!(Mask::EMPTY.expanded_by_distance(3).is_empty());
+    }
+
+    #[test]
+    fn within_manhattan_distance() {
+        assert_eq!(Mask::within_manhattan_distance(0, 0, 0), Mask::new([0b1]));
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b111,
+            0b011,
+            0b001,
+        ]);
+        assert_eq!(Mask::within_manhattan_distance(0, 0, 2), expected);
+
+        let mask = Mask::within_manhattan_distance(9, 9, 3);
+        assert_eq!(mask.count(), 25);
+        for y in 0..19usize {
+            for x in 0..19usize {
+                let distance = x.abs_diff(9) + y.abs_diff(9);
+                assert_eq!(mask.get(x, y), distance <= 3);
+            }
+        }
+
+        assert_eq!(Mask::within_manhattan_distance(0, 18, 100), Mask::FILLED);
+    }
+
+    #[test]
+    fn boundary_between() {
+        #[rustfmt::skip]
+        let group = Mask::new([
+            0b0110,
+            0b0110,
+            0b0110,
+        ]);
+
+        #[rustfmt::skip]
+        let territory = Mask::new([
+            0b0001,
+            0b0001,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b0010,
+            0b0010,
+        ]);
+
+        assert_eq!(Mask::boundary_between(&group, &territory), expected);
+        assert!(Mask::boundary_between(&group, &Mask::EMPTY).is_empty());
+        // Points of `a` that are also in `b` count as adjacent
+        assert_eq!(Mask::boundary_between(&group, &group), group);
+    }
+
+    #[test]
+    fn outline() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b0000,
+            0b0110,
+            0b0000,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b0110,
+            0b1001,
+            0b0110,
+        ]);
+
+        assert_eq!(mask.outline(), expected);
+    }
+
+    #[test]
+    fn outline_at_border() {
+        let mut mask = Mask::EMPTY;
+        mask.set(18, 18);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(17, 18);
+        expected.set(18, 17);
+
+        assert_eq!(mask.outline(), expected);
+    }
+
+    #[test]
+    fn perimeter() {
+        #[rustfmt::skip]
+        let group = Mask::new([
+            0b0000,
+            0b0110,
+            0b0000,
+        ]);
+
+        #[rustfmt::skip]
+        let opponent = Mask::new([
+            0b0100,
+            0b0001,
+        ]);
+
+        assert_eq!(group.perimeter(&Mask::EMPTY), 6);
+        assert_eq!(group.perimeter(&opponent), 4);
+    }
+
+    #[test]
+    fn u32_array_round_trip() {
+        let mut rows = [0; 19];
+        rows[0] = 0b101;
+        rows[7] = 0b1111111111111111111;
+        rows[18] = 1 << 18;
+        let mask = Mask::from_u32_array(rows);
+        assert!(mask.get(2, 0));
+        assert!(mask.get(18, 18));
+        assert_eq!(mask.to_u32_array(), rows);
+        assert_eq!(mask.as_u32_slice(), &rows);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_u32_array_rejects_off_board_bits() {
+        let mut rows = [0; 19];
+        rows[3] = 1 << 19;
+        let _ = Mask::from_u32_array(rows);
+    }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn bitwise_operators() {
+        let a = Mask::new([0b1100, 0b0110]);
+        let b = Mask::new([0b1010, 0b0011]);
+
+        assert_eq!(&a & &b, Mask::new([0b1000, 0b0010]));
+        assert_eq!(&a | &b, Mask::new([0b1110, 0b0111]));
+        assert_eq!(&a ^ &b, Mask::new([0b0110, 0b0101]));
+        assert_eq!(a & b, &a & &b);
+        assert_eq!(a | &b, &a | b);
+        assert_eq!(!&a, !a);
+
+        let mut c = a;
+        c &= &b;
+        c |= Mask::new([0, 0, 0b1]);
+        c ^= &a;
+        assert_eq!(c, Mask::new([0b0100, 0b0100, 0b1]));
+    }
+
+    #[test]
+    fn set_relations() {
+        let a = Mask::new([0b0100, 0b0010]);
+        let b = Mask::new([0b0110, 0b0011]);
+        let c = Mask::new([0b1001, 0b1000]);
+
+        assert!(a.is_subset_of(&b));
+        assert!(b.is_superset_of(&a));
+        assert!(!b.is_subset_of(&a));
+        assert!(!a.is_superset_of(&b));
+        assert!(a.is_subset_of(&a));
+        assert!(Mask::EMPTY.is_subset_of(&a));
+        assert!(a.is_subset_of(&Mask::FILLED));
+
+        assert!(a.is_disjoint_from(&c));
+        assert!(b.is_disjoint_from(&c));
+        assert!(!a.is_disjoint_from(&b));
+        assert!(Mask::EMPTY.is_disjoint_from(&Mask::EMPTY));
+
+        for (x, y) in [(a, b), (b, a), (a, c), (c, Mask::FILLED), (Mask::EMPTY, c)] {
+            assert_eq!(x.is_subset_of(&y), y.is_superset_of(&x));
+        }
+    }
+
+    #[test]
+    fn transpose() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b0111,
+            0b0001,
+            0b1000,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b011,
+            0b001,
+            0b001,
+            0b100,
+        ]);
+
+        assert_eq!(mask.transpose(), expected);
+        assert_eq!(mask.transpose().transpose(), mask);
+
+        let mut corner = Mask::EMPTY;
+        corner.set(18, 0);
+        let mut transposed = Mask::EMPTY;
+        transposed.set(0, 18);
+        assert_eq!(corner.transpose(), transposed);
+        assert_eq!(Mask::FILLED.transpose(), Mask::FILLED);
+    }
+
+    #[test]
+    fn anti_transpose() {
+        let mut mask = Mask::EMPTY;
+        mask.set(0, 0);
+        mask.set(4, 7);
+        mask.set(18, 0);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(18, 18);
+        expected.set(11, 14);
+        expected.set(18, 0);
+        assert_eq!(mask.anti_transpose(), expected);
+        assert_eq!(mask.anti_transpose().anti_transpose(), mask);
+        assert_eq!(mask.anti_transpose(), mask.transpose().rotate_180());
+        assert_eq!(
+            Mask::DIAGONAL_A1_T19.anti_transpose(),
+            Mask::DIAGONAL_A1_T19
+        );
+    }
+
+    #[test]
+    fn connected_components() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b10011,
+            0b10010,
+            0b00000,
+            0b01100,
+        ]);
+
+        let components = mask.connected_components();
+        assert_eq!(
+            components,
+            vec![
+                Mask::new([0b00011, 0b00010]),
+                Mask::new([0b10000, 0b10000]),
+                Mask::new([0b00000, 0b00000, 0b00000, 0b01100]),
+            ]
+        );
+        assert_eq!(mask.component_count(), 3);
+        assert!(Mask::EMPTY.connected_components().is_empty());
+    }
+
+    #[test]
+    fn largest_and_smallest_component() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b10011,
+            0b10010,
+            0b00000,
+            0b01100,
+        ]);
+
+        assert_eq!(
+            mask.largest_component(),
+            Some(Mask::new([0b00011, 0b00010]))
+        );
+        // Both two-stone groups tie, so the one containing (2, 3) wins over (4, 0)
+        assert_eq!(
+            mask.smallest_component(),
+            Some(Mask::new([0b00000, 0b00000, 0b00000, 0b01100]))
+        );
+
+        let ties = Mask::new([0b101]);
+        assert_eq!(ties.largest_component(), Some(Mask::new([0b001])));
+        assert_eq!(ties.smallest_component(), Some(Mask::new([0b001])));
+
+        assert_eq!(Mask::EMPTY.largest_component(), None);
+        assert_eq!(Mask::EMPTY.smallest_component(), None);
+    }
+
+    #[test]
+    fn intersection_and_union_count() {
+        let a = Mask::new([0b0111, 0b0010]);
+        let b = Mask::new([0b1100, 0b0011, 0b1]);
+        assert_eq!(a.intersection_count(&b), (a & b).count());
+        assert_eq!(a.intersection_count(&b), 2);
+        assert_eq!(a.union_count(&b), (a | b).count());
+        assert_eq!(a.union_count(&b), 7);
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        #[rustfmt::skip]
+        let mut mask = Mask::new([
+            0b0001,
+            0b1010,
+        ]);
+        mask.set(18, 18);
+
+        let fen = mask.to_fen();
+        assert!(fen.starts_with("b18/1b1b15/19/"));
+        assert!(fen.ends_with("/18b"));
+        assert_eq!(Mask::from_fen(&fen), Ok(mask));
+        let state = crate::State::from_fen(&fen).unwrap();
+        assert_eq!(state.black, mask);
+        assert_eq!(Mask::from_fen(&Mask::EMPTY.to_fen()), Ok(Mask::EMPTY));
+    }
+
+    #[test]
+    fn fen_errors() {
+        assert_eq!(Mask::from_fen("19/19"), Err(FenError::RowCount(2)));
+
+        let mut rows = vec!["19"; 19];
+        rows[4] = "18";
+        assert_eq!(Mask::from_fen(&rows.join("/")), Err(FenError::RowLength(4)));
+        rows[4] = "10b9";
+        assert_eq!(Mask::from_fen(&rows.join("/")), Err(FenError::RowLength(4)));
+        rows[0] = "99999999999999999999999";
+        assert_eq!(Mask::from_fen(&rows.join("/")), Err(FenError::RowLength(0)));
+        rows[0] = "0b18";
+        assert_eq!(Mask::from_fen(&rows.join("/")), Err(FenError::ZeroRun(0)));
+        rows[0] = "0019";
+        assert_eq!(Mask::from_fen(&rows.join("/")), Err(FenError::ZeroRun(0)));
+        rows[0] = "19";
+        rows[4] = "9w9";
+        assert_eq!(
+            Mask::from_fen(&rows.join("/")),
+            Err(FenError::InvalidCharacter('w'))
+        );
+    }
+
+    #[test]
+    fn row_iter() {
+        let mut mask = Mask::new([0b1, 0b10, 0b100]);
+        let nonempty: Vec<_> = mask.row_iter().filter(|(_, row)| ***row != 0).collect();
+        assert_eq!(
+            nonempty,
+            vec![
+                (0, &MaskRow::new(0b1)),
+                (1, &MaskRow::new(0b10)),
+                (2, &MaskRow::new(0b100)),
+            ]
+        );
+
+        for (y, row) in mask.row_iter_mut() {
+            row.set(y);
+        }
+        assert!(mask.get(18, 18));
+        assert!(mask.get(0, 0));
+        assert!(mask.get(2, 2));
+        assert!(mask.get(1, 1));
+        assert_eq!(mask.count(), 19);
+    }
+
+    #[test]
+    fn get_and_set_row() {
+        let mut mask = Mask::EMPTY;
+        mask.set_row(4, MaskRow::FILLED);
+        assert_eq!(mask.get_row(4), MaskRow::FILLED);
+        assert_eq!(mask.get_row(3), MaskRow::EMPTY);
+        assert_eq!(mask.count(), 19);
+
+        mask.set_row(4, MaskRow::new(0b101));
+        assert_eq!(mask.count(), 2);
+        assert!(mask.get(2, 4));
+    }
+
+    #[test]
+    fn diagonals() {
+        assert_eq!(Mask::DIAGONAL_A19_T1.count(), 19);
+        assert_eq!(Mask::DIAGONAL_A1_T19.count(), 19);
+        for i in 0..19 {
+            assert!(Mask::DIAGONAL_A19_T1.get(i, i));
+            assert!(Mask::DIAGONAL_A1_T19.get(i, 18 - i));
+        }
+        assert_eq!(Mask::DIAGONAL_A19_T1.mirror_x(), Mask::DIAGONAL_A1_T19);
+
+        assert_eq!(Mask::diagonal(0), Mask::DIAGONAL_A19_T1);
+        assert_eq!(Mask::diagonal(18).count(), 1);
+        assert!(Mask::diagonal(18).get(18, 0));
+        assert!(Mask::diagonal(-18).get(0, 18));
+        assert_eq!(Mask::diagonal(3).count(), 16);
+        assert!(Mask::diagonal(-3).get(0, 3));
+        assert!(Mask::diagonal(19).is_empty());
+        assert!(Mask::diagonal(-40).is_empty());
+    }
+
+    #[test]
+    fn get_and_set_column() {
+        let mut mask = Mask::EMPTY;
+        mask.set_column(3, *MaskRow::FILLED);
+        assert_eq!(mask.column(3), *MaskRow::FILLED);
+        assert_eq!(mask.column(2), 0);
+        assert_eq!(mask.count(), 19);
+
+        mask.set(4, 7);
+        mask.set_column(3, 0b101);
+        assert_eq!(mask.count(), 3);
+        assert!(mask.get(3, 0));
+        assert!(mask.get(3, 2));
+        assert_eq!(mask.column(3), 0b101);
+        assert_eq!(mask.column(4), 1 << 7);
+        assert_eq!(mask.transpose().get_row(3), MaskRow::new(0b101));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_column_rejects_off_board_bits() {
+        let mut mask = Mask::EMPTY;
+        mask.set_column(0, 1 << 19);
+    }
+
+    #[test]
+    fn center_of_mass() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b0011,
+            0b0001,
+            0b0000,
+            0b1000,
+        ]);
+        assert_eq!(mask.center_of_mass(), Some((1.0, 1.0)));
+        assert_eq!(mask.centroid(), Some((1, 1)));
+
+        let pair = Mask::new([0b11]);
+        assert_eq!(pair.center_of_mass(), Some((0.5, 0.0)));
+        assert_eq!(Mask::FILLED.centroid(), Some((9, 9)));
+        assert_eq!(Mask::EMPTY.center_of_mass(), None);
+        assert_eq!(Mask::EMPTY.centroid(), None);
+    }
+
+    #[test]
+    fn density() {
+        assert_eq!(Mask::EMPTY.density(), 0.0);
+        assert_eq!(Mask::FILLED.density(), 1.0);
+
+        let mask = Mask::new([0b0011]);
+        let region = Mask::new([0b1111]);
+        assert_eq!(mask.density(), 2.0 / 361.0);
+        assert_eq!(mask.density_in_region(&region), 0.5);
+        assert_eq!(mask.density_in_region(&Mask::EMPTY), 0.0);
+    }
+
+    #[test]
+    fn expand_into() {
+        #[rustfmt::skip]
+        let source = Mask::new([
+            0b000,
+            0b010,
+        ]);
+
+        #[rustfmt::skip]
+        let expected = Mask::new([
+            0b010,
+            0b111,
+            0b010,
+        ]);
+
+        let mut target = Mask::FILLED;
+        source.expand_into(&Mask::FILLED, &mut target);
+        assert_eq!(target, expected);
+        assert_eq!(source, Mask::new([0b000, 0b010]));
+    }
+
+    #[test]
+    fn bounding_box() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b00000,
+            0b00100,
+            0b00000,
+            0b10010,
+        ]);
+        assert_eq!(mask.bounding_box(), Some((1, 1, 4, 3)));
+        assert_eq!(Mask::FILLED.bounding_box(), Some((0, 0, 18, 18)));
+        assert_eq!(Mask::new([0b1]).bounding_box(), Some((0, 0, 0, 0)));
+        assert_eq!(Mask::EMPTY.bounding_box(), None);
+    }
+
+    #[test]
+    fn row_union_and_intersection() {
+        let mask = Mask::new([0b0011, 0b0100, 0b1000]);
+        assert_eq!(mask.row_union(), MaskRow::new(0b1111));
+        assert!(mask.row_intersection().is_empty());
+        assert!(Mask::EMPTY.row_union().is_empty());
+        assert_eq!(Mask::FILLED.row_intersection(), MaskRow::FILLED);
+
+        let mut column = Mask::EMPTY;
+        for y in 0..19 {
+            column.set(4, y);
+        }
+        assert_eq!(column.row_intersection(), MaskRow::new(0b10000));
+    }
+
+    #[test]
+    fn popcount_per_row_and_column() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b0111,
+            0b0001,
+            0b1001,
+        ]);
+
+        let mut rows = [0; 19];
+        rows[..3].copy_from_slice(&[3, 1, 2]);
+        assert_eq!(mask.popcount_per_row(), rows);
+
+        let mut columns = [0; 19];
+        columns[..4].copy_from_slice(&[3, 1, 1, 1]);
+        assert_eq!(mask.popcount_per_column(), columns);
+
+        assert_eq!(Mask::FILLED.popcount_per_row(), [19; 19]);
+        assert_eq!(Mask::FILLED.popcount_per_column(), [19; 19]);
+
+        #[allow(deprecated)]
+        let counts = (mask.count_per_row(), mask.count_per_column());
+        assert_eq!(counts, (rows, columns));
+    }
+
+    #[test]
+    fn fold_rows() {
+        let mask = Mask::new([0b0011, 0b0100, 0b1000]);
+        let union = mask.fold_rows(MaskRow::EMPTY, |acc, &row| acc | row);
+        assert_eq!(union, MaskRow::new(0b1111));
+        assert_eq!(mask.fold_rows(0, |acc, row| acc + row.count_ones()), 4);
+
+        assert!(mask.any_row_nonzero());
+        assert!(!Mask::EMPTY.any_row_nonzero());
+        assert!(mask.all_rows_match(|row| row.count_ones() <= 2));
+        assert!(!mask.all_rows_match(|row| **row != 0));
+    }
+
+    #[test]
+    fn rotations() {
+        let mut mask = Mask::EMPTY;
+        mask.set(18, 0);
+        mask.set(1, 0);
+        mask.set(2, 5);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(18, 18);
+        expected.set(18, 1);
+        expected.set(13, 2);
+        assert_eq!(mask.rotate_90_cw(), expected);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(0, 0);
+        expected.set(0, 17);
+        expected.set(5, 16);
+        assert_eq!(mask.rotate_90_ccw(), expected);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(0, 18);
+        expected.set(17, 18);
+        expected.set(16, 13);
+        assert_eq!(mask.rotate_180(), expected);
+
+        assert_eq!(mask.rotate_90_cw().rotate_90_ccw(), mask);
+        assert_eq!(mask.rotate_90_cw().rotate_90_cw(), mask.rotate_180());
+        assert_eq!(
+            mask.rotate_90_cw()
+                .rotate_90_cw()
+                .rotate_90_cw()
+                .rotate_90_cw(),
+            mask
+        );
+    }
+
+    #[test]
+    fn mirrors() {
+        let mut mask = Mask::EMPTY;
+        mask.set(0, 0);
+        mask.set(4, 7);
+        mask.set(18, 18);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(18, 0);
+        expected.set(14, 7);
+        expected.set(0, 18);
+        assert_eq!(mask.mirror_x(), expected);
+
+        let mut expected = Mask::EMPTY;
+        expected.set(0, 18);
+        expected.set(4, 11);
+        expected.set(18, 0);
+        assert_eq!(mask.mirror_y(), expected);
+
+        assert_eq!(mask.mirror_x().mirror_x(), mask);
+        assert_eq!(mask.mirror_y().mirror_y(), mask);
+        assert_eq!(Mask::FILLED.mirror_x(), Mask::FILLED);
+        assert_eq!(Mask::FILLED.rotate_90_cw(), Mask::FILLED);
+    }
+
+    #[test]
+    fn not_stays_on_board() {
+        assert_eq!(!Mask::EMPTY, Mask::FILLED);
+        assert_eq!(!Mask::FILLED, Mask::EMPTY);
+        assert_eq!(!Mask::INTERIOR, Mask::BORDER);
+
+        let mut mask = Mask::EMPTY;
+        *mask[3] |= 1 << 20;
+        assert_eq!(!mask, Mask::FILLED);
+        assert_eq!((!Mask::EMPTY).count(), 361);
+    }
+
+    #[test]
+    fn from_iter() {
+        let mask: Mask = [(0, 0), (1, 1), (2, 2), (1, 1)].into_iter().collect();
+        assert_eq!(mask, Mask::new([0b001, 0b010, 0b100]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_iter_out_of_range() {
+        let _: Mask = [(19, 0)].into_iter().collect();
+    }
+
+    #[test]
+    fn named_set_operations() {
+        let a = Mask::new([0b0110, 0b0001]);
+        let b = Mask::new([0b0011, 0b0100]);
+        assert_eq!(a.intersection(&b), a & b);
+        assert_eq!(a.union(&b), a | b);
+    }
+
+    #[test]
+    fn hamming_distance() {
+        let a = Mask::new([0b0110, 0b0001]);
+        let b = Mask::new([0b0011, 0b0001, 0b1]);
+        assert_eq!(a.hamming_distance(&b), 3);
+        assert_eq!(b.hamming_distance(&a), 3);
+        assert_eq!(a.hamming_distance(&a), 0);
+    }
+
+    #[test]
+    fn expand_unconstrained() {
+        let mut mask = Mask::new([0b010]);
+        let expanded = mask.expanded_unconstrained();
+        mask.expand_unconstrained();
+        assert_eq!(mask, expanded);
+        assert_eq!(mask, Mask::new([0b111, 0b010]));
+    }
+
+    #[test]
+    fn border_and_interior() {
+        assert_eq!(Mask::BORDER.count(), 72);
+        assert_eq!(Mask::INTERIOR.count(), 17 * 17);
+        assert!(Mask::BORDER.is_disjoint_from(&Mask::INTERIOR));
+        assert_eq!(Mask::BORDER | Mask::INTERIOR, Mask::FILLED);
+        assert!(Mask::BORDER.get(0, 7));
+        assert!(Mask::BORDER.get(18, 7));
+        assert!(Mask::BORDER.get(7, 18));
+        assert!(Mask::INTERIOR.get(1, 1));
+    }
+
+    #[test]
+    fn toggle() {
+        let mut mask = Mask::new([0b1]);
+        mask.toggle(0, 0);
+        mask.toggle(18, 18);
+        assert!(!mask.get(0, 0));
+        assert!(mask.get(18, 18));
+        assert_eq!(mask.count(), 1);
+    }
+
+    #[test]
+    fn u64_array_round_trip() {
+        let mut mask = Mask::new([0b1, 0b11]);
+        mask.set(18, 3);
+        mask.set(0, 4);
+        mask.set(8, 3);
+        mask.set(18, 18);
+
+        let words = mask.to_u64_array();
+        assert_eq!(words[0] & 0b111 << 19, 0b11 << 19);
+        assert_eq!(words[1], 0b11 << 11 | 0b10);
+        assert_eq!(words[5] >> 40, 1);
+        assert_eq!(Mask::from_u64_array(words), Ok(mask));
+        assert_eq!(Mask::FILLED.to_u64_array()[5], (1 << 41) - 1);
+        assert_eq!(
+            Mask::from_u64_array(Mask::FILLED.to_u64_array()),
+            Ok(Mask::FILLED)
+        );
+    }
+
+    #[test]
+    fn u64_array_rejects_padding_bits() {
+        let mut words = [0; 6];
+        words[5] = 1 << 41;
+        assert_eq!(
+            Mask::from_u64_array(words),
+            Err(MaskDecodeError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn neighbor_count_mask() {
+        #[rustfmt::skip]
+        let mask = Mask::new([
+            0b010,
+            0b101,
+            0b010,
+        ]);
+
+        let counts = mask.neighbor_count_mask();
+        assert_eq!(counts[1][1], 4);
+        assert_eq!(counts[0][0], 2);
+        assert_eq!(counts[0][1], 0);
+        assert_eq!(counts[0][2], 2);
+        assert_eq!(counts[3][1], 1);
+        assert_eq!(counts[18][18], 0);
+        assert_eq!(Mask::FILLED.neighbor_count_mask()[0][0], 2);
+        assert_eq!(Mask::FILLED.neighbor_count_mask()[9][0], 3);
+        assert_eq!(Mask::FILLED.neighbor_count_mask()[9][9], 4);
+    }
 }
@@ -1,26 +1,30 @@
 use std::{
-    fmt::{self, Debug, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     ops::{
-        BitAnd, BitAndAssign, BitOr, BitOrAssign, Deref, DerefMut, Not, Shl, ShlAssign, Shr,
-        ShrAssign,
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Deref, DerefMut, Not, Shl,
+        ShlAssign, Shr, ShrAssign,
     },
 };
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[repr(transparent)]
 pub struct MaskRow(u32);
 
 impl MaskRow {
     pub const EMPTY: Self = Self(0);
     pub const FILLED: Self = Self(0b1111111111111111111);
 
-    pub fn new(value: u32) -> Self {
+    #[must_use]
+    pub const fn new(value: u32) -> Self {
         assert!(
-            &value < &Self::FILLED,
+            value & !Self::FILLED.0 == 0,
             "Cannot have a row with more than 19 stones"
         );
         Self(value)
     }
 
+    #[must_use]
     pub fn get(&self, i: usize) -> bool {
         assert!(i <= 18);
         self.0 >> i & 1 == 1
@@ -36,9 +40,34 @@ impl MaskRow {
         self.0 &= !(1 << i);
     }
 
+    pub fn toggle(&mut self, i: usize) {
+        assert!(i <= 18);
+        self.0 ^= 1 << i;
+    }
+
+    /// Spreads each set bit to its horizontal neighbours. Bit 18 spills over
+    /// into bit 19, which is off the board, so the result should be masked
+    /// with a stencil before use. See [`MaskRow::expand_bounded`].
+    #[must_use]
     pub fn expand(self) -> Self {
         self << 1 | self | self >> 1
     }
+
+    #[must_use]
+    pub fn expand_bounded(self) -> Self {
+        self.expand() & Self::FILLED
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub fn count_runs(&self) -> u32 {
+        // Count the lowest bit of each run, which has no set bit below it
+        (self.0 & !(self.0 << 1)).count_ones()
+    }
 }
 
 impl Deref for MaskRow {
@@ -83,6 +112,20 @@ impl BitOrAssign for MaskRow {
     }
 }
 
+impl BitXor for MaskRow {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl BitXorAssign for MaskRow {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0
+    }
+}
+
 impl Shl<usize> for MaskRow {
     type Output = Self;
 
@@ -115,16 +158,23 @@ impl Not for MaskRow {
     type Output = Self;
 
     fn not(self) -> Self::Output {
-        Self(!self.0)
+        Self(!self.0 & Self::FILLED.0)
     }
 }
 
-impl Debug for MaskRow {
+// Rows print most significant bit first, so column 18 is leftmost.
+impl Display for MaskRow {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{:019b}", self.0)
     }
 }
 
+impl Debug for MaskRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,4 +187,61 @@ mod tests {
         row.set(13);
         assert_eq!(format!("{row:?}"), "0000010010000001000")
     }
+
+    #[test]
+    fn display_matches_debug() {
+        let row = MaskRow::new(0b1000000000000000011);
+        assert_eq!(format!("{row}"), "1000000000000000011");
+        assert_eq!(format!("{row}"), format!("{row:?}"));
+    }
+
+    #[test]
+    fn new_accepts_filled_row() {
+        assert_eq!(MaskRow::new(0b1111111111111111111), MaskRow::FILLED);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_rejects_off_board_bits() {
+        let _ = MaskRow::new(1 << 19);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_rejects_high_bits() {
+        let _ = MaskRow::new(0x80000000);
+    }
+
+    #[test]
+    fn not_stays_on_board() {
+        assert_eq!(!MaskRow::EMPTY, MaskRow::FILLED);
+        assert_eq!(!MaskRow::FILLED, MaskRow::EMPTY);
+        assert_eq!(!MaskRow::new(0b1), MaskRow::new(0b1111111111111111110));
+    }
+
+    #[test]
+    fn toggle() {
+        let mut row = MaskRow::EMPTY;
+        row.toggle(4);
+        assert!(row.get(4));
+        row.toggle(4);
+        assert_eq!(row, MaskRow::EMPTY);
+    }
+
+    #[test]
+    fn count_runs() {
+        assert_eq!(MaskRow::new(0b1001101).count_runs(), 3);
+        assert_eq!(MaskRow::EMPTY.count_runs(), 0);
+        assert_eq!(MaskRow::FILLED.count_runs(), 1);
+        assert_eq!(MaskRow::new(0b1010101010101010101).count_runs(), 10);
+    }
+
+    #[test]
+    fn expand_bounded() {
+        assert_eq!(MaskRow::new(0b0100).expand_bounded(), MaskRow::new(0b1110));
+        assert_eq!(MaskRow::new(1).expand_bounded(), MaskRow::new(0b11));
+        let edge = MaskRow::new(1 << 18);
+        assert_eq!(*edge.expand(), 0b111 << 17);
+        assert_eq!(edge.expand_bounded(), MaskRow::new(0b11 << 17));
+    }
 }
@@ -0,0 +1,72 @@
+use crate::{Move, Tree};
+use rand::{seq::SliceRandom, Rng};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RandomPlayer {
+    pub allow_eye_fills: bool,
+}
+
+impl RandomPlayer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn generate_move(&self, tree: &Tree, rng: &mut impl Rng) -> Move {
+        let state = tree.current();
+        let mut moves = tree.legal_moves();
+        if !self.allow_eye_fills {
+            moves.retain(|&mv| match mv {
+                Move::Place(x, y) => !state.is_eye(x, y, tree.to_play()),
+                Move::Pass => true,
+            });
+        }
+        moves.choose(rng).copied().unwrap_or(Move::Pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Mask, State};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn plays_legal_moves() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut tree = Tree::empty();
+        let player = RandomPlayer::new();
+        for _ in 0..50 {
+            let mv = player.generate_move(&tree, &mut rng);
+            assert_eq!(tree.apply(mv), Ok(()));
+        }
+    }
+
+    #[test]
+    fn avoids_own_eyes() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b1110,
+            0b1011,
+            0b1110,
+        ]);
+        let mut state = State::from_masks(black, Mask::EMPTY).unwrap();
+        state.set_multiple((0..19).flat_map(|y| (4..19).map(move |x| (x, y, Some(Color::Black)))));
+        state.set_multiple((3..19).flat_map(|y| (0..4).map(move |x| (x, y, Some(Color::Black)))));
+        let tree = Tree::new(state, Color::Black);
+
+        let player = RandomPlayer {
+            allow_eye_fills: true,
+        };
+        let eye_fills = [Move::Place(0, 0), Move::Place(2, 1), Move::Place(0, 2)];
+        assert_eq!(tree.legal_moves().len(), eye_fills.len());
+        for seed in 0..64 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            assert_eq!(
+                RandomPlayer::new().generate_move(&tree, &mut rng),
+                Move::Pass
+            );
+            assert!(eye_fills.contains(&player.generate_move(&tree, &mut rng)));
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::Color;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Score {
+    pub black: f32,
+    pub white: f32,
+}
+
+impl Score {
+    #[must_use]
+    pub fn new(black: f32, white: f32) -> Self {
+        Self { black, white }
+    }
+
+    #[must_use]
+    pub fn winner(&self) -> Option<Color> {
+        if self.black > self.white {
+            Some(Color::Black)
+        } else if self.white > self.black {
+            Some(Color::White)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub fn margin(&self) -> f32 {
+        (self.black - self.white).abs()
+    }
+
+    #[must_use]
+    pub fn apply_komi(&self, komi: f32) -> Score {
+        Self {
+            black: self.black,
+            white: self.white + komi,
+        }
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.winner() {
+            Some(Color::Black) => write!(f, "B+{}", self.margin()),
+            Some(Color::White) => write!(f, "W+{}", self.margin()),
+            None => write!(f, "Draw"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winner_and_margin() {
+        let score = Score::new(184.0, 177.0);
+        assert_eq!(score.winner(), Some(Color::Black));
+        assert_eq!(score.margin(), 7.0);
+
+        let score = score.apply_komi(7.5);
+        assert_eq!(score.winner(), Some(Color::White));
+        assert_eq!(score.margin(), 0.5);
+
+        assert_eq!(Score::new(3.0, 3.0).winner(), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Score::new(10.0, 3.5).to_string(), "B+6.5");
+        assert_eq!(Score::new(3.0, 3.5).to_string(), "W+0.5");
+        assert_eq!(Score::new(3.0, 3.0).to_string(), "Draw");
+    }
+}
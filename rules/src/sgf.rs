@@ -0,0 +1,560 @@
+use crate::{Color, Handicap, Move, PlaceStoneError, State, Tree};
+use std::{iter::Peekable, str::CharIndices};
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum SgfError {
+    #[error("Malformed SGF at byte {0}")]
+    Syntax(usize),
+    #[error("Only 19x19 boards are supported, found size {0}")]
+    UnsupportedBoardSize(String),
+    #[error("Invalid value [{value}] for property {property}")]
+    InvalidValue { property: String, value: String },
+    #[error("Move {index} is illegal: {error}")]
+    IllegalMove {
+        index: usize,
+        error: PlaceStoneError,
+    },
+}
+
+type Properties = Vec<(String, Vec<String>)>;
+
+impl Tree {
+    pub fn from_sgf(sgf: &str) -> Result<Self, SgfError> {
+        let nodes = Parser::new(sgf).main_line()?;
+        let root = nodes.first().ok_or(SgfError::Syntax(0))?;
+
+        let mut state = State::default();
+        let mut komi = 0.0;
+        let mut to_play = Color::Black;
+        let mut handicap = 0;
+        for (property, values) in root {
+            let value = values[0].as_str();
+            match property.as_str() {
+                "SZ" => {
+                    if value.trim() != "19" {
+                        return Err(SgfError::UnsupportedBoardSize(value.to_string()));
+                    }
+                }
+                "KM" => komi = parse_number(property, value)?,
+                "HA" => handicap = parse_number(property, value)?,
+                "PL" => to_play = parse_color(property, value)?,
+                "AB" | "AW" | "AE" => apply_setup(&mut state, property, values)?,
+                _ => {}
+            }
+        }
+
+        let has_setup = root.iter().any(|(property, _)| property == "AB");
+        if handicap >= 2 && !has_setup {
+            let stones = Handicap::stones(handicap).map_err(|_| SgfError::InvalidValue {
+                property: "HA".to_string(),
+                value: handicap.to_string(),
+            })?;
+            for &(x, y) in stones {
+                state.set(x, y, Some(Color::Black));
+            }
+        }
+        if handicap >= 2 && !root.iter().any(|(property, _)| property == "PL") {
+            to_play = Color::White;
+        }
+
+        let mut tree = Tree::from_state(state, to_play);
+        tree.komi = komi;
+
+        let mut index = 0;
+        for (i, node) in nodes.iter().enumerate() {
+            // Setup properties after the root become a setup node of their own
+            if i > 0 {
+                tree.apply_setup_node(node)?;
+            }
+            for (property, values) in node {
+                let color = match property.as_str() {
+                    "B" => Color::Black,
+                    "W" => Color::White,
+                    _ => continue,
+                };
+                let mv = parse_move(property, &values[0])?;
+                tree.set_to_play(color);
+                tree.apply(mv)
+                    .map_err(|error| SgfError::IllegalMove { index, error })?;
+                index += 1;
+            }
+        }
+
+        Ok(tree)
+    }
+
+    fn apply_setup_node(&mut self, properties: &Properties) -> Result<(), SgfError> {
+        let mut state = *self.current();
+        let mut has_setup = false;
+        let mut to_play = None;
+        for (property, values) in properties {
+            match property.as_str() {
+                "AB" | "AW" | "AE" => {
+                    apply_setup(&mut state, property, values)?;
+                    has_setup = true;
+                }
+                "PL" => to_play = Some(parse_color(property, &values[0])?),
+                _ => {}
+            }
+        }
+
+        let to_play = to_play.unwrap_or(self.to_play());
+        if has_setup {
+            self.push_node(state, 0, None, to_play);
+        } else {
+            self.set_to_play(to_play);
+        }
+        Ok(())
+    }
+}
+
+fn apply_setup(state: &mut State, property: &str, values: &[String]) -> Result<(), SgfError> {
+    let color = match property {
+        "AB" => Some(Color::Black),
+        "AW" => Some(Color::White),
+        _ => None,
+    };
+    for value in values {
+        for (x, y) in parse_points(property, value)? {
+            state.set(x, y, None);
+            state.set(x, y, color);
+        }
+    }
+    Ok(())
+}
+
+impl Tree {
+    #[must_use]
+    pub fn to_sgf(&self) -> String {
+        let mut sgf = String::from("(");
+        for index in self.path_from_root() {
+            self.write_node(&mut sgf, index);
+        }
+        sgf.push(')');
+        sgf
+    }
+
+    #[must_use]
+    pub fn to_sgf_with_variations(&self) -> String {
+        let mut children = vec![vec![]; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate().skip(1) {
+            children[node.parent].push(index);
+        }
+        let mut sgf = String::new();
+        self.write_variation(&mut sgf, &children, 0);
+        sgf
+    }
+
+    fn write_variation(&self, sgf: &mut String, children: &[Vec<usize>], mut index: usize) {
+        sgf.push('(');
+        loop {
+            self.write_node(sgf, index);
+            match children[index].as_slice() {
+                [] => break,
+                &[child] => index = child,
+                variations => {
+                    for &child in variations {
+                        self.write_variation(sgf, children, child);
+                    }
+                    break;
+                }
+            }
+        }
+        sgf.push(')');
+    }
+
+    fn write_node(&self, sgf: &mut String, index: usize) {
+        let node = &self.nodes[index];
+        sgf.push(';');
+        match (index, node.mv) {
+            (0, _) => {
+                sgf.push_str(&format!("GM[1]FF[4]SZ[19]KM[{}]", self.komi));
+                write_setup(sgf, &State::default(), &node.state);
+                if node.to_play != Color::Black {
+                    write_player(sgf, node.to_play);
+                }
+            }
+            (_, Some((color, mv))) => {
+                sgf.push(match color {
+                    Color::Black => 'B',
+                    Color::White => 'W',
+                });
+                sgf.push('[');
+                if let Move::Place(x, y) = mv {
+                    sgf.push(format_coordinate(x));
+                    sgf.push(format_coordinate(y));
+                }
+                sgf.push(']');
+            }
+            (_, None) => {
+                let parent = &self.nodes[node.parent];
+                write_setup(sgf, &parent.state, &node.state);
+                if node.to_play != parent.to_play {
+                    write_player(sgf, node.to_play);
+                }
+            }
+        }
+
+        let Some(annotation) = self.annotation_at(index) else {
+            return;
+        };
+        if let Some(comment) = &annotation.comment {
+            sgf.push_str("C[");
+            for c in comment.chars() {
+                if c == ']' || c == '\\' {
+                    sgf.push('\\');
+                }
+                sgf.push(c);
+            }
+            sgf.push(']');
+        }
+        if let Some(evaluation) = annotation.evaluation {
+            sgf.push_str(&format!("V[{evaluation}]"));
+        }
+    }
+}
+
+fn write_setup(sgf: &mut String, before: &State, after: &State) {
+    let added = |color| after.stones(color) & !before.stones(color);
+    let removed = !before.empty() & after.empty();
+    for (property, points) in [
+        ("AB", added(Color::Black)),
+        ("AW", added(Color::White)),
+        ("AE", removed),
+    ] {
+        if points.is_empty() {
+            continue;
+        }
+        sgf.push_str(property);
+        for (x, y) in points.iter_positions() {
+            sgf.push('[');
+            sgf.push(format_coordinate(x));
+            sgf.push(format_coordinate(y));
+            sgf.push(']');
+        }
+    }
+}
+
+fn write_player(sgf: &mut String, color: Color) {
+    sgf.push_str(match color {
+        Color::Black => "PL[B]",
+        Color::White => "PL[W]",
+    });
+}
+
+fn format_coordinate(i: usize) -> char {
+    (b'a' + i as u8) as char
+}
+
+fn invalid(property: &str, value: &str) -> SgfError {
+    SgfError::InvalidValue {
+        property: property.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(property: &str, value: &str) -> Result<T, SgfError> {
+    value.trim().parse().map_err(|_| invalid(property, value))
+}
+
+fn parse_color(property: &str, value: &str) -> Result<Color, SgfError> {
+    match value.trim() {
+        "B" | "b" => Ok(Color::Black),
+        "W" | "w" => Ok(Color::White),
+        _ => Err(invalid(property, value)),
+    }
+}
+
+fn parse_coordinate(c: char) -> Option<usize> {
+    let i = (c as usize).checked_sub('a' as usize)?;
+    (i <= 18).then_some(i)
+}
+
+fn parse_point(property: &str, value: &str) -> Result<(usize, usize), SgfError> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(x), Some(y), None) => parse_coordinate(x)
+            .zip(parse_coordinate(y))
+            .ok_or_else(|| invalid(property, value)),
+        _ => Err(invalid(property, value)),
+    }
+}
+
+fn parse_points(property: &str, value: &str) -> Result<Vec<(usize, usize)>, SgfError> {
+    match value.split_once(':') {
+        Some((from, to)) => {
+            let (x0, y0) = parse_point(property, from)?;
+            let (x1, y1) = parse_point(property, to)?;
+            let xs = x0.min(x1)..=x0.max(x1);
+            let ys = y0.min(y1)..=y0.max(y1);
+            Ok(ys.flat_map(|y| xs.clone().map(move |x| (x, y))).collect())
+        }
+        None => Ok(vec![parse_point(property, value)?]),
+    }
+}
+
+fn parse_move(property: &str, value: &str) -> Result<Move, SgfError> {
+    match value {
+        "" | "tt" => Ok(Move::Pass),
+        _ => {
+            let (x, y) = parse_point(property, value)?;
+            Ok(Move::Place(x, y))
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(sgf: &'a str) -> Self {
+        Self {
+            chars: sgf.char_indices().peekable(),
+            len: sgf.len(),
+        }
+    }
+
+    fn main_line(mut self) -> Result<Vec<Properties>, SgfError> {
+        let mut nodes = vec![];
+        self.game_tree(Some(&mut nodes))?;
+        Ok(nodes)
+    }
+
+    fn position(&mut self) -> usize {
+        self.chars.peek().map_or(self.len, |&(i, _)| i)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                return Some(c);
+            }
+            self.chars.next();
+        }
+        None
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SgfError> {
+        if self.peek() == Some(expected) {
+            self.chars.next();
+            Ok(())
+        } else {
+            Err(SgfError::Syntax(self.position()))
+        }
+    }
+
+    fn game_tree(&mut self, mut nodes: Option<&mut Vec<Properties>>) -> Result<(), SgfError> {
+        self.expect('(')?;
+        while self.peek() == Some(';') {
+            self.chars.next();
+            let node = self.node()?;
+            if let Some(nodes) = nodes.as_deref_mut() {
+                nodes.push(node);
+            }
+        }
+
+        let mut is_main_line = true;
+        while self.peek() == Some('(') {
+            let target = if is_main_line {
+                nodes.as_deref_mut()
+            } else {
+                None
+            };
+            self.game_tree(target)?;
+            is_main_line = false;
+        }
+        self.expect(')')
+    }
+
+    fn node(&mut self) -> Result<Properties, SgfError> {
+        let mut properties = vec![];
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            let mut identifier = String::new();
+            while let Some(&(_, c)) = self.chars.peek() {
+                if !c.is_ascii_alphabetic() {
+                    break;
+                }
+                // Old SGF versions allow lowercase letters in identifiers
+                if c.is_ascii_uppercase() {
+                    identifier.push(c);
+                }
+                self.chars.next();
+            }
+
+            let mut values = vec![];
+            while self.peek() == Some('[') {
+                values.push(self.value()?);
+            }
+            if values.is_empty() {
+                return Err(SgfError::Syntax(self.position()));
+            }
+            properties.push((identifier, values));
+        }
+        Ok(properties)
+    }
+
+    fn value(&mut self) -> Result<String, SgfError> {
+        self.expect('[')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, ']')) => return Ok(value),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => return Err(SgfError::Syntax(self.len)),
+                },
+                Some((_, c)) => value.push(c),
+                None => return Err(SgfError::Syntax(self.len)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeAnnotation;
+
+    #[test]
+    fn main_line() {
+        let sgf = "(;GM[1]FF[4]SZ[19]KM[6.5];B[pd];W[dp](;B[pp];W[]) (;B[dd]))";
+        let tree = Tree::from_sgf(sgf).unwrap();
+        assert_eq!(tree.komi, 6.5);
+        assert_eq!(tree.current().color_at(15, 3), Some(Color::Black));
+        assert_eq!(tree.current().color_at(3, 15), Some(Color::White));
+        assert_eq!(tree.current().color_at(15, 15), Some(Color::Black));
+        assert_eq!(tree.current().color_at(3, 3), None);
+        assert_eq!(tree.to_play(), Color::Black);
+        assert_eq!(tree.node_count(), 5);
+    }
+
+    #[test]
+    fn setup_and_handicap() {
+        let sgf = "(;SZ[19]HA[2]AB[dd][pp]AW[aa:ba];W[qq])";
+        let tree = Tree::from_sgf(sgf).unwrap();
+        let state = tree.current();
+        assert_eq!(state.color_at(3, 3), Some(Color::Black));
+        assert_eq!(state.color_at(15, 15), Some(Color::Black));
+        assert_eq!(state.color_at(0, 0), Some(Color::White));
+        assert_eq!(state.color_at(1, 0), Some(Color::White));
+        assert_eq!(state.color_at(16, 16), Some(Color::White));
+
+        let tree = Tree::from_sgf("(;HA[4])").unwrap();
+        assert_eq!(tree.current().black.count(), 4);
+        assert_eq!(tree.to_play(), Color::White);
+    }
+
+    #[test]
+    fn escaped_values() {
+        let sgf = r"(;C[a \] tricky [comment\\];B[aa])";
+        let tree = Tree::from_sgf(sgf).unwrap();
+        assert_eq!(tree.current().color_at(0, 0), Some(Color::Black));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Tree::from_sgf("(;B[aa]"), Err(SgfError::Syntax(7)));
+        assert_eq!(Tree::from_sgf("B[aa]"), Err(SgfError::Syntax(0)));
+        assert_eq!(
+            Tree::from_sgf("(;SZ[9])"),
+            Err(SgfError::UnsupportedBoardSize("9".to_string()))
+        );
+        assert_eq!(
+            Tree::from_sgf("(;B[aa];W[aa])"),
+            Err(SgfError::IllegalMove {
+                index: 1,
+                error: PlaceStoneError::AlreadyExists
+            })
+        );
+        assert_eq!(
+            Tree::from_sgf("(;B[zz])"),
+            Err(SgfError::InvalidValue {
+                property: "B".to_string(),
+                value: "zz".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn to_sgf() {
+        let mut tree = Tree::empty();
+        tree.komi = 6.5;
+        tree.place_stone(15, 3).unwrap();
+        tree.annotate_current(NodeAnnotation {
+            comment: Some("Star [point]".to_string()),
+            evaluation: Some(0.5),
+        });
+        tree.pass();
+        assert_eq!(
+            tree.to_sgf(),
+            r"(;GM[1]FF[4]SZ[19]KM[6.5];B[pd]C[Star [point\]]V[0.5];W[])"
+        );
+
+        let tree = Tree::from_sgf(&tree.to_sgf()).unwrap();
+        assert_eq!(tree.komi, 6.5);
+        assert_eq!(tree.current().color_at(15, 3), Some(Color::Black));
+        assert_eq!(tree.node_count(), 3);
+    }
+
+    #[test]
+    fn to_sgf_writes_player_to_move() {
+        let tree = Tree::from_state(State::default(), Color::White);
+        assert_eq!(tree.to_sgf(), "(;GM[1]FF[4]SZ[19]KM[0]PL[W])");
+        let tree = Tree::from_sgf(&tree.to_sgf()).unwrap();
+        assert_eq!(tree.to_play(), Color::White);
+    }
+
+    #[test]
+    fn to_sgf_with_variations() {
+        let sgf = "(;GM[1]FF[4]SZ[19]KM[6.5]AB[dd]PL[W];W[pd](;B[pp];W[])(;B[dp]))";
+        let mut tree = Tree::from_sgf(sgf).unwrap();
+        tree.go_to(1);
+        tree.place_stone(3, 15).unwrap();
+        assert_eq!(tree.to_sgf_with_variations(), sgf);
+        assert_eq!(
+            tree.to_sgf(),
+            "(;GM[1]FF[4]SZ[19]KM[6.5]AB[dd]PL[W];W[pd];B[dp])"
+        );
+
+        let mut tree = Tree::empty();
+        tree.place_handicap(2).unwrap();
+        assert_eq!(
+            tree.to_sgf_with_variations(),
+            "(;GM[1]FF[4]SZ[19]KM[0];AB[dd][pp]PL[W])"
+        );
+    }
+
+    #[test]
+    fn handicap_round_trip() {
+        let mut tree = Tree::empty();
+        tree.place_handicap(4).unwrap();
+        tree.place_stone(10, 10).unwrap();
+        let sgf = tree.to_sgf();
+        assert_eq!(
+            sgf,
+            "(;GM[1]FF[4]SZ[19]KM[0];AB[dd][pd][dp][pp]PL[W];W[kk])"
+        );
+
+        let imported = Tree::from_sgf(&sgf).unwrap();
+        assert_eq!(imported.current(), tree.current());
+        assert_eq!(imported.current().black.count(), 4);
+        assert_eq!(imported.to_play(), Color::Black);
+        assert_eq!(imported.node_count(), 3);
+        assert_eq!(imported.to_sgf(), sgf);
+    }
+
+    #[test]
+    fn setup_node_removes_stones() {
+        let sgf = "(;GM[1]FF[4]SZ[19]KM[0]AB[aa][bb];AE[aa]AW[bb])";
+        let tree = Tree::from_sgf(sgf).unwrap();
+        assert_eq!(tree.current().color_at(0, 0), None);
+        assert_eq!(tree.current().color_at(1, 1), Some(Color::White));
+        assert_eq!(tree.current().black.count(), 0);
+        assert_eq!(
+            tree.to_sgf(),
+            "(;GM[1]FF[4]SZ[19]KM[0]AB[aa][bb];AW[bb]AE[aa])"
+        );
+    }
+}
@@ -1,5 +1,16 @@
-use crate::{color::Color, mask::Mask};
-use std::fmt::{self, Debug, Formatter};
+use crate::{
+    capture::Capture,
+    color::Color,
+    fen::{self, FenError},
+    mask::{neighbors, Mask},
+    mask_row::MaskRow,
+    score::Score,
+    PlaceStoneError,
+};
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug, Display, Formatter},
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct State {
@@ -7,8 +18,23 @@ pub struct State {
     pub(crate) white: Mask,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemeaiInfo {
+    pub group_a_libs: u32,
+    pub group_b_libs: u32,
+    pub shared_libs: u32,
+}
+
 impl State {
-    pub fn new(black: Mask, white: Mask) -> Self {
+    pub fn from_masks(black: Mask, white: Mask) -> Result<Self, StateError> {
+        let state = Self::from_masks_unchecked(black, white);
+        state.validate()?;
+        Ok(state)
+    }
+
+    // The caller is responsible for keeping the masks disjoint and on the board
+    #[must_use]
+    pub fn from_masks_unchecked(black: Mask, white: Mask) -> Self {
         Self { black, white }
     }
 
@@ -26,7 +52,463 @@ impl State {
         }
     }
 
-    pub fn get(&self, x: usize, y: usize) -> Option<Color> {
+    pub fn set_multiple(
+        &mut self,
+        stones: impl IntoIterator<Item = (usize, usize, Option<Color>)>,
+    ) {
+        for (x, y, color) in stones {
+            self.set(x, y, color);
+        }
+        debug_assert_eq!(self.validate(), Ok(()));
+    }
+
+    pub fn validate(&self) -> Result<(), StateError> {
+        if !(self.black & self.white).is_empty() {
+            return Err(StateError::Overlap);
+        }
+        let occupied = self.black | self.white;
+        if occupied.rows().any(|row| **row & !*MaskRow::FILLED != 0) {
+            return Err(StateError::OutOfBounds);
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_legal_move(
+        &self,
+        x: usize,
+        y: usize,
+        color: Color,
+        previous_state: Option<&State>,
+    ) -> bool {
+        self.play(x, y, color, previous_state).is_ok()
+    }
+
+    #[must_use]
+    pub fn legal_moves_mask(&self, color: Color, previous_state: Option<&State>) -> Mask {
+        let mut legal = Mask::EMPTY;
+        let empty = self.empty();
+        for y in 0..19 {
+            for x in 0..19 {
+                if empty.get(x, y) && self.is_legal_move(x, y, color, previous_state) {
+                    legal.set(x, y);
+                }
+            }
+        }
+        legal
+    }
+
+    #[must_use]
+    pub fn is_self_capture_at(&self, x: usize, y: usize, color: Color) -> bool {
+        matches!(
+            self.play(x, y, color, None),
+            Err(PlaceStoneError::SelfCapture)
+        )
+    }
+
+    pub fn copy_with_move(
+        &self,
+        x: usize,
+        y: usize,
+        color: Color,
+    ) -> Result<State, PlaceStoneError> {
+        self.play(x, y, color, None)
+    }
+
+    pub(crate) fn play(
+        &self,
+        x: usize,
+        y: usize,
+        color: Color,
+        previous: Option<&State>,
+    ) -> Result<State, PlaceStoneError> {
+        let mut state = *self;
+        state.apply_move(x, y, color, previous)?;
+        Ok(state)
+    }
+
+    pub fn apply_move(
+        &mut self,
+        x: usize,
+        y: usize,
+        color: Color,
+        previous: Option<&State>,
+    ) -> Result<u32, PlaceStoneError> {
+        assert!(x <= 18);
+        assert!(y <= 18);
+
+        if self.color_at(x, y).is_some() {
+            return Err(PlaceStoneError::AlreadyExists);
+        }
+
+        let mut state = *self;
+        state.set(x, y, Some(color));
+
+        let mut capture = Capture::new(&mut state, color);
+        let captures = capture.try_capture_neighbors(x, y);
+
+        if captures == 0 {
+            let group = state.stones(color).flood(x, y);
+            if !group.has_a_liberty(state.stones(color.opposite())) {
+                return Err(PlaceStoneError::SelfCapture);
+            }
+        }
+
+        if previous == Some(&state) {
+            return Err(PlaceStoneError::Ko);
+        }
+
+        *self = state;
+        Ok(captures)
+    }
+
+    #[must_use]
+    pub fn ladder_check(&self, x: usize, y: usize, color: Color) -> bool {
+        if self.color_at(x, y) != Some(color) {
+            return false;
+        }
+        self.is_ladder_captured(x, y, color, MAX_LADDER_DEPTH, &mut HashSet::new())
+    }
+
+    fn is_ladder_captured(
+        &self,
+        x: usize,
+        y: usize,
+        color: Color,
+        depth: usize,
+        path: &mut HashSet<State>,
+    ) -> bool {
+        let liberties: Vec<_> = self.liberties(x, y, color).iter_positions().collect();
+        let &[(escape_x, escape_y)] = liberties.as_slice() else {
+            return false;
+        };
+        if depth == 0 || !path.insert(*self) {
+            return false;
+        }
+
+        let is_captured = match self.play(escape_x, escape_y, color, None) {
+            Err(_) => true,
+            Ok(escaped) => {
+                let liberties: Vec<_> = escaped.liberties(x, y, color).iter_positions().collect();
+                match liberties.len() {
+                    0 | 1 => true,
+                    2 => liberties.into_iter().any(|(atari_x, atari_y)| {
+                        match escaped.play(atari_x, atari_y, color.opposite(), None) {
+                            Ok(next) => next.is_ladder_captured(x, y, color, depth - 1, path),
+                            Err(_) => false,
+                        }
+                    }),
+                    _ => false,
+                }
+            }
+        };
+
+        path.remove(self);
+        is_captured
+    }
+
+    #[must_use]
+    pub fn group_liberties_mask(&self, x: usize, y: usize) -> Option<Mask> {
+        let color = self.color_at(x, y)?;
+        Some(self.liberties(x, y, color))
+    }
+
+    #[must_use]
+    pub fn semeai_info(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> Option<SemeaiInfo> {
+        let color_a = self.color_at(x1, y1)?;
+        let color_b = self.color_at(x2, y2)?;
+        if color_a == color_b {
+            return None;
+        }
+        let libs_a = self.liberties(x1, y1, color_a);
+        let libs_b = self.liberties(x2, y2, color_b);
+        Some(SemeaiInfo {
+            group_a_libs: libs_a.count(),
+            group_b_libs: libs_b.count(),
+            shared_libs: libs_a.intersection_count(&libs_b),
+        })
+    }
+
+    #[must_use]
+    pub fn mutual_atari(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> bool {
+        matches!(
+            self.semeai_info(x1, y1, x2, y2),
+            Some(SemeaiInfo {
+                group_a_libs: 1,
+                group_b_libs: 1,
+                shared_libs: 1,
+            })
+        )
+    }
+
+    fn liberties(&self, x: usize, y: usize, color: Color) -> Mask {
+        self.stones(color).flood(x, y).outline() & self.empty()
+    }
+
+    #[must_use]
+    pub fn empty(&self) -> Mask {
+        !(self.black | self.white)
+    }
+
+    pub fn iter_black(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.black.iter_positions()
+    }
+
+    pub fn iter_white(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.white.iter_positions()
+    }
+
+    pub fn iter_empty(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.empty().iter_positions()
+    }
+
+    #[must_use]
+    pub fn is_eye(&self, x: usize, y: usize, color: Color) -> bool {
+        self.color_at(x, y).is_none()
+            && neighbors(x, y).all(|(x, y)| self.color_at(x, y) == Some(color))
+    }
+
+    #[must_use]
+    pub fn is_true_eye(&self, x: usize, y: usize, color: Color) -> bool {
+        if !self.is_eye(x, y, color) {
+            return false;
+        }
+        let opponent = Some(color.opposite());
+        let opponent_diagonals = [
+            (x.wrapping_sub(1), y.wrapping_sub(1)),
+            (x + 1, y.wrapping_sub(1)),
+            (x.wrapping_sub(1), y + 1),
+            (x + 1, y + 1),
+        ]
+        .into_iter()
+        .filter(|&(x, y)| x <= 18 && y <= 18 && self.color_at(x, y) == opponent)
+        .count();
+        // Eyes on the edge are false if any diagonal is taken by the opponent
+        let on_edge = x == 0 || x == 18 || y == 0 || y == 18;
+        opponent_diagonals <= usize::from(!on_edge)
+    }
+
+    #[must_use]
+    pub fn clearly_alive(&self, color: Color) -> Mask {
+        let eyes: Mask = self
+            .iter_empty()
+            .filter(|&(x, y)| self.is_true_eye(x, y, color))
+            .collect();
+        let mut alive = Mask::EMPTY;
+        for group in self.stones(color).connected_components() {
+            if Mask::boundary_between(&eyes, &group).count() >= 2 {
+                alive |= group;
+            }
+        }
+        alive
+    }
+
+    #[must_use]
+    pub fn count_eyes(&self, color: Color) -> u32 {
+        self.iter_empty()
+            .filter(|&(x, y)| self.is_eye(x, y, color))
+            .count() as u32
+    }
+
+    #[must_use]
+    pub fn count_true_eyes(&self, color: Color) -> u32 {
+        self.iter_empty()
+            .filter(|&(x, y)| self.is_true_eye(x, y, color))
+            .count() as u32
+    }
+
+    #[must_use]
+    pub fn territory(&self, color: Color) -> Mask {
+        let stones = self.stones(color);
+        let mut territory = Mask::EMPTY;
+        for region in self.empty().connected_components() {
+            let border = region.outline();
+            if !border.is_empty() && border.is_subset_of(stones) {
+                territory |= region;
+            }
+        }
+        territory
+    }
+
+    #[must_use]
+    pub fn territory_border_mask(&self, color: Color) -> Mask {
+        let open = self.empty().flood_from_mask(&Mask::BORDER);
+        self.territory(color) & open
+    }
+
+    #[must_use]
+    pub fn score_area(&self, komi: f32) -> Score {
+        let black = self.black.count() + self.territory(Color::Black).count();
+        let white = self.white.count() + self.territory(Color::White).count();
+        Score::new(black as f32, white as f32).apply_komi(komi)
+    }
+
+    #[must_use]
+    pub fn score_area_with_dead(&self, dead: Mask, komi: f32) -> Score {
+        debug_assert!(
+            dead.is_subset_of(&(self.black | self.white)),
+            "Dead stone mask contains empty intersections"
+        );
+        let mut state = *self;
+        state.remove_stones_by_mask(dead);
+        state.score_area(komi)
+    }
+
+    // Prisoners are not tracked by the board position, so this only counts
+    // surrounded empty points.
+    #[must_use]
+    pub fn score_territory(&self, komi: f32) -> Score {
+        let black = self.territory(Color::Black).count();
+        let white = self.territory(Color::White).count();
+        Score::new(black as f32, white as f32).apply_komi(komi)
+    }
+
+    pub fn remove_stones_by_mask(&mut self, mask: Mask) -> (u32, u32) {
+        let black_removed = self.black.intersection_count(&mask);
+        let white_removed = self.white.intersection_count(&mask);
+        self.black &= !mask;
+        self.white &= !mask;
+        (black_removed, white_removed)
+    }
+
+    #[must_use]
+    pub fn hamming_distance(&self, other: &State) -> u32 {
+        self.black.hamming_distance(&other.black) + self.white.hamming_distance(&other.white)
+    }
+
+    #[must_use]
+    pub fn canonical_form(&self) -> State {
+        let mut rotated = *self;
+        let mut best = *self;
+        for _ in 0..4 {
+            let mirrored = rotated.map_masks(Mask::mirror_x);
+            for candidate in [rotated, mirrored] {
+                if candidate.sort_key() < best.sort_key() {
+                    best = candidate;
+                }
+            }
+            rotated = rotated.map_masks(Mask::rotate_90_cw);
+        }
+        best
+    }
+
+    #[must_use]
+    pub fn anti_transpose(&self) -> State {
+        self.map_masks(Mask::anti_transpose)
+    }
+
+    fn map_masks(&self, f: impl Fn(&Mask) -> Mask) -> State {
+        State::from_masks_unchecked(f(&self.black), f(&self.white))
+    }
+
+    fn sort_key(&self) -> ([u32; 19], [u32; 19]) {
+        (self.black.to_u32_array(), self.white.to_u32_array())
+    }
+
+    #[must_use]
+    pub fn to_compact_bytes(&self) -> [u8; 91] {
+        let mut bytes = [0; 91];
+        for (offset, mask) in [(0, &self.black), (361, &self.white)] {
+            for y in 0..19 {
+                for x in 0..19 {
+                    if mask.get(x, y) {
+                        let bit = offset + y * 19 + x;
+                        bytes[bit / 8] |= 1 << (bit % 8);
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_compact_bytes(bytes: [u8; 91]) -> Result<State, StateError> {
+        // Only 722 of the 728 bits hold intersections
+        if bytes[90] >> 2 != 0 {
+            return Err(StateError::OutOfBounds);
+        }
+        let mut state = State::default();
+        for (offset, mask) in [(0, &mut state.black), (361, &mut state.white)] {
+            for y in 0..19 {
+                for x in 0..19 {
+                    let bit = offset + y * 19 + x;
+                    if bytes[bit / 8] >> (bit % 8) & 1 == 1 {
+                        mask.set(x, y);
+                    }
+                }
+            }
+        }
+        state.validate()?;
+        Ok(state)
+    }
+
+    #[must_use]
+    pub fn to_fen(&self) -> String {
+        fen::encode(|x, y| match self.color_at(x, y) {
+            Some(Color::Black) => Some('b'),
+            Some(Color::White) => Some('w'),
+            None => None,
+        })
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut state = Self::default();
+        fen::decode(fen, |x, y, c| {
+            let color = match c {
+                'b' => Color::Black,
+                'w' => Color::White,
+                _ => return Err(FenError::InvalidCharacter(c)),
+            };
+            state.set(x, y, Some(color));
+            Ok(())
+        })?;
+        Ok(state)
+    }
+
+    #[must_use]
+    pub fn all_groups(&self) -> Vec<(Color, Mask)> {
+        let black = self.black.connected_components().into_iter();
+        let white = self.white.connected_components().into_iter();
+        black
+            .map(|group| (Color::Black, group))
+            .chain(white.map(|group| (Color::White, group)))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn group_count(&self, color: Color) -> usize {
+        self.stones(color).component_count()
+    }
+
+    // A liberty shared by several groups is counted once for each of them
+    #[must_use]
+    pub fn total_liberties(&self, color: Color) -> u32 {
+        let empty = self.empty();
+        self.stones(color)
+            .connected_components()
+            .iter()
+            .map(|group| (group.outline() & empty).count())
+            .sum()
+    }
+
+    #[must_use]
+    pub fn distinct_liberties(&self, color: Color) -> u32 {
+        (self.stones(color).outline() & self.empty()).count()
+    }
+
+    #[must_use]
+    pub fn influence_density(&self, color: Color, radius: usize) -> f32 {
+        self.stones(color).expanded_by_distance(radius).density()
+    }
+
+    pub(crate) fn stones(&self, color: Color) -> &Mask {
+        match color {
+            Color::Black => &self.black,
+            Color::White => &self.white,
+        }
+    }
+
+    #[must_use]
+    pub fn color_at(&self, x: usize, y: usize) -> Option<Color> {
         assert!(x <= 18);
         assert!(y <= 18);
 
@@ -38,22 +520,758 @@ impl State {
             None
         }
     }
+
+    #[must_use]
+    pub fn is_black_at(&self, x: usize, y: usize) -> bool {
+        self.color_at(x, y) == Some(Color::Black)
+    }
+
+    #[must_use]
+    pub fn is_white_at(&self, x: usize, y: usize) -> bool {
+        self.color_at(x, y) == Some(Color::White)
+    }
+}
+
+const MAX_LADDER_DEPTH: usize = 64;
+
+impl FromIterator<(usize, usize, Color)> for State {
+    fn from_iter<T: IntoIterator<Item = (usize, usize, Color)>>(iter: T) -> Self {
+        let mut state = Self::default();
+        state.set_multiple(iter.into_iter().map(|(x, y, color)| (x, y, Some(color))));
+        state
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum StateError {
+    #[error("An intersection is occupied by both black and white stones")]
+    Overlap,
+    #[error("A stone is set outside of the board")]
+    OutOfBounds,
+}
+
+const COLUMN_LABELS: &str = "ABCDEFGHJKLMNOPQRST";
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let header: Vec<_> = COLUMN_LABELS.chars().map(String::from).collect();
+        writeln!(f, "   {}", header.join(" "))?;
+        for y in 0..19 {
+            write!(f, "{:>2}", 19 - y)?;
+            for x in 0..19 {
+                let c = match self.color_at(x, y) {
+                    Some(Color::Black) => 'X',
+                    Some(Color::White) => 'O',
+                    None => '.',
+                };
+                write!(f, " {c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 impl Debug for State {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "\n")?;
+        writeln!(f)?;
         for y in 0..19 {
             for x in 0..19 {
-                let c = match self.get(x, y) {
+                let c = match self.color_at(x, y) {
                     Some(Color::Black) => 'b',
                     Some(Color::White) => 'w',
                     None => '.',
                 };
                 write!(f, "{c}")?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    fn hash_of(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn any_state() -> impl Strategy<Value = State> {
+        let rows = proptest::array::uniform19(0u32..1 << 19);
+        (rows.clone(), rows).prop_map(|(black, white)| {
+            let black = Mask::from_u32_array(black);
+            let white = Mask::from_u32_array(white) & !black;
+            State::from_masks_unchecked(black, white)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn equal_states_hash_equally(a in any_state()) {
+            // Same stones, placed white first and in reverse order
+            let white = a.white.bit_scan_reverse().map(|(x, y)| (x, y, Some(Color::White)));
+            let black = a.black.bit_scan_reverse().map(|(x, y)| (x, y, Some(Color::Black)));
+            let mut b = State::default();
+            b.set_multiple(white.chain(black));
+            prop_assert_eq!(b, a);
+            prop_assert_eq!(hash_of(&b), hash_of(&a));
+            prop_assert_eq!(hash_of(&b.black), hash_of(&a.black));
+        }
+
+        #[test]
+        fn states_built_by_set_hash_like_constructed(state in any_state()) {
+            let mut built = State::default();
+            for y in 0..19 {
+                for x in 0..19 {
+                    built.set(x, y, state.color_at(x, y));
+                }
+            }
+            prop_assert_eq!(built, state);
+            prop_assert_eq!(hash_of(&built), hash_of(&state));
+        }
+
+        #[test]
+        fn anti_transpose_is_an_involution(state in any_state()) {
+            prop_assert_eq!(state.anti_transpose().anti_transpose(), state);
+        }
+    }
+
+    #[test]
+    fn color_at() {
+        let state = State::from_masks(Mask::new([0b01]), Mask::new([0b10])).unwrap();
+        assert_eq!(state.color_at(0, 0), Some(Color::Black));
+        assert_eq!(state.color_at(1, 0), Some(Color::White));
+        assert_eq!(state.color_at(2, 0), None);
+        assert!(state.is_black_at(0, 0));
+        assert!(!state.is_black_at(1, 0));
+        assert!(state.is_white_at(1, 0));
+        assert!(!state.is_white_at(2, 0));
+    }
+
+    #[test]
+    fn set_multiple() {
+        let mut state = State::from_masks(Mask::new([0b11]), Mask::EMPTY).unwrap();
+        state.set_multiple([
+            (0, 0, None),
+            (2, 0, Some(Color::Black)),
+            (0, 1, Some(Color::White)),
+        ]);
+        assert_eq!(state.black, Mask::new([0b110]));
+        assert_eq!(state.white, Mask::new([0b0, 0b1]));
+    }
+
+    #[test]
+    fn validate() {
+        let state = State::from_masks(Mask::new([0b01]), Mask::new([0b10])).unwrap();
+        assert_eq!(state.validate(), Ok(()));
+
+        let state = State::from_masks_unchecked(Mask::new([0b11]), Mask::new([0b10]));
+        assert_eq!(state.validate(), Err(StateError::Overlap));
+
+        let mut black = Mask::EMPTY;
+        *black[3] |= 1 << 20;
+        let state = State::from_masks_unchecked(black, Mask::EMPTY);
+        assert_eq!(state.validate(), Err(StateError::OutOfBounds));
+    }
+
+    #[test]
+    fn from_masks_rejects_invalid_masks() {
+        assert_eq!(
+            State::from_masks(Mask::new([0b11]), Mask::new([0b10])),
+            Err(StateError::Overlap)
+        );
+
+        let mut black = Mask::EMPTY;
+        *black[3] |= 1 << 20;
+        assert_eq!(
+            State::from_masks(black, Mask::EMPTY),
+            Err(StateError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn set_multiple_catches_overlap() {
+        let mut state = State::default();
+        state.set_multiple([(0, 0, Some(Color::Black)), (0, 0, Some(Color::White))]);
+    }
+
+    #[test]
+    fn all_groups() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b1001,
+            0b1000,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b0110,
+            0b0111,
+        ]);
+
+        let state = State::from_masks(black, white).unwrap();
+        assert_eq!(
+            state.all_groups(),
+            vec![
+                (Color::Black, Mask::new([0b0001])),
+                (Color::Black, Mask::new([0b1000, 0b1000])),
+                (Color::White, white),
+            ]
+        );
+        assert_eq!(state.group_count(Color::Black), 2);
+        assert_eq!(state.group_count(Color::White), 1);
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let mut state = State::default();
+        state.set(3, 0, Some(Color::Black));
+        state.set(4, 0, Some(Color::White));
+        state.set(15, 16, Some(Color::White));
+
+        let fen = state.to_fen();
+        assert!(fen.starts_with("3bw14/19/"));
+        assert_eq!(State::from_fen(&fen), Ok(state));
+        assert_eq!(
+            State::from_fen(&fen.replace('w', "x")),
+            Err(FenError::InvalidCharacter('x'))
+        );
+    }
+
+    #[test]
+    fn remove_stones_by_mask() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b0011,
+            0b0001,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b1100,
+            0b0000,
+            0b1111,
+        ]);
+
+        #[rustfmt::skip]
+        let dead = Mask::new([
+            0b0110,
+            0b0011,
+            0b1001,
+        ]);
+
+        let mut state = State::from_masks(black, white).unwrap();
+        assert_eq!(state.remove_stones_by_mask(dead), (2, 3));
+        assert_eq!(state.black, Mask::new([0b0001]));
+        assert_eq!(state.white, Mask::new([0b1000, 0b0000, 0b0110]));
+    }
+
+    #[test]
+    fn influence_density() {
+        let mut state = State::default();
+        state.set(9, 9, Some(Color::Black));
+        state.set(0, 0, Some(Color::White));
+        assert_eq!(state.influence_density(Color::Black, 0), 1.0 / 361.0);
+        assert_eq!(state.influence_density(Color::Black, 2), 13.0 / 361.0);
+        assert_eq!(state.influence_density(Color::White, 1), 3.0 / 361.0);
+        assert_eq!(state.influence_density(Color::White, usize::MAX), 1.0);
+    }
+
+    #[test]
+    fn canonical_form() {
+        let mut state = State::default();
+        state.set(15, 3, Some(Color::Black));
+        state.set(16, 2, Some(Color::White));
+        let canonical = state.canonical_form();
+
+        let mut rotated = state;
+        for _ in 0..4 {
+            rotated = rotated.map_masks(Mask::rotate_90_cw);
+            assert_eq!(rotated.canonical_form(), canonical);
+            assert_eq!(
+                rotated.map_masks(Mask::mirror_x).canonical_form(),
+                canonical
+            );
+        }
+
+        let mut expected = State::default();
+        expected.set(3, 15, Some(Color::Black));
+        expected.set(2, 16, Some(Color::White));
+        assert_eq!(canonical, expected);
+    }
+
+    #[test]
+    fn from_iter() {
+        let state: State = [
+            (0, 0, Color::Black),
+            (1, 0, Color::White),
+            (0, 0, Color::Black),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            state,
+            State::from_masks(Mask::new([0b01]), Mask::new([0b10])).unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn from_iter_conflicting_colors() {
+        let _: State = [(0, 0, Color::Black), (0, 0, Color::White)]
+            .into_iter()
+            .collect();
+    }
+
+    #[test]
+    fn scoring() {
+        let mut state = State::default();
+        for y in 0..19 {
+            state.set(3, y, Some(Color::Black));
+            state.set(4, y, Some(Color::White));
+        }
+        state.set(10, 10, Some(Color::Black));
+
+        assert_eq!(state.territory(Color::Black).count(), 3 * 19);
+        assert_eq!(state.territory(Color::White).count(), 0);
+        assert_eq!(state.score_area(6.5), Score::new(77.0, 25.5));
+        assert_eq!(state.score_territory(0.5), Score::new(57.0, 0.5));
+        assert_eq!(State::default().score_area(0.0), Score::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn iter_stones() {
+        let state: State = [
+            (4, 1, Color::Black),
+            (2, 3, Color::Black),
+            (0, 0, Color::White),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(state.iter_black().collect::<Vec<_>>(), vec![(4, 1), (2, 3)]);
+        assert_eq!(state.iter_white().collect::<Vec<_>>(), vec![(0, 0)]);
+        assert_eq!(state.iter_empty().count(), 358);
+        assert_eq!(state.iter_empty().next(), Some((1, 0)));
+    }
+
+    #[test]
+    fn semeai_info() {
+        let state: State = [
+            (0, 0, Color::Black),
+            (1, 0, Color::Black),
+            (0, 1, Color::White),
+        ]
+        .into_iter()
+        .collect();
+        let expected = SemeaiInfo {
+            group_a_libs: 2,
+            group_b_libs: 2,
+            shared_libs: 1,
+        };
+        assert_eq!(state.semeai_info(1, 0, 0, 1), Some(expected));
+        assert_eq!(state.semeai_info(0, 0, 1, 0), None);
+        assert_eq!(state.semeai_info(0, 0, 5, 5), None);
+    }
+
+    #[test]
+    fn mutual_atari() {
+        let state: State = [
+            (0, 0, Color::Black),
+            (1, 0, Color::Black),
+            (0, 2, Color::Black),
+            (0, 1, Color::White),
+            (2, 0, Color::White),
+        ]
+        .into_iter()
+        .collect();
+        assert!(state.mutual_atari(0, 0, 0, 1));
+        assert!(state.mutual_atari(0, 1, 1, 0));
+        assert!(!state.mutual_atari(1, 0, 2, 0));
+        assert!(!state.mutual_atari(0, 0, 0, 2));
+        assert!(!state.mutual_atari(0, 0, 5, 5));
+    }
+
+    #[test]
+    fn count_eyes() {
+        let black = [
+            (1, 0),
+            (0, 1),
+            (2, 1),
+            (1, 2),
+            (5, 4),
+            (4, 5),
+            (6, 5),
+            (5, 6),
+        ];
+        let mut state: State = black
+            .into_iter()
+            .map(|(x, y)| (x, y, Color::Black))
+            .collect();
+        assert!(state.is_eye(0, 0, Color::Black));
+        assert!(state.is_eye(1, 1, Color::Black));
+        assert!(state.is_eye(5, 5, Color::Black));
+        assert!(!state.is_eye(5, 5, Color::White));
+        assert_eq!(state.count_eyes(Color::Black), 3);
+        assert_eq!(state.count_true_eyes(Color::Black), 3);
+        assert_eq!(state.count_eyes(Color::White), 0);
+
+        // One opponent diagonal is tolerated in the middle of the board
+        state.set(4, 4, Some(Color::White));
+        assert!(state.is_true_eye(5, 5, Color::Black));
+        state.set(6, 6, Some(Color::White));
+        assert!(!state.is_true_eye(5, 5, Color::Black));
+
+        // Two opponent diagonals make the eye at (1, 1) false
+        state.set(2, 2, Some(Color::White));
+        assert!(state.is_true_eye(1, 1, Color::Black));
+        state.set(0, 2, Some(Color::White));
+        assert!(!state.is_true_eye(1, 1, Color::Black));
+        assert_eq!(state.count_eyes(Color::Black), 3);
+        assert_eq!(state.count_true_eyes(Color::Black), 1);
+    }
+
+    #[test]
+    fn clearly_alive() {
+        // A black wall along the top edge with two eyes at (1, 0) and (3, 0)
+        let mut state = State::default();
+        for x in 0..6 {
+            state.set(x, 1, Some(Color::Black));
+        }
+        state.set(0, 0, Some(Color::Black));
+        state.set(2, 0, Some(Color::Black));
+        state.set(4, 0, Some(Color::Black));
+        state.set(5, 0, Some(Color::Black));
+        // A black group with a single eye
+        for (x, y) in [(10, 9), (9, 10), (11, 10), (10, 11)] {
+            state.set(x, y, Some(Color::Black));
+        }
+        for x in 0..7 {
+            state.set(x, 2, Some(Color::White));
+        }
+        state.set(6, 0, Some(Color::White));
+        state.set(6, 1, Some(Color::White));
+
+        let mut expected = Mask::EMPTY;
+        for x in 0..6 {
+            expected.set(x, 1);
+        }
+        for x in [0, 2, 4, 5] {
+            expected.set(x, 0);
+        }
+        assert_eq!(state.clearly_alive(Color::Black), expected);
+        assert!(state.clearly_alive(Color::White).is_empty());
+
+        // Joining both eyes into one space leaves the wall without eyes
+        state.set(2, 0, None);
+        assert!(state.clearly_alive(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn territory_border_mask() {
+        let mut state = State::default();
+        for y in 0..19 {
+            state.set(3, y, Some(Color::Black));
+            state.set(4, y, Some(Color::White));
+        }
+        // An enclosed white eye away from the edge
+        for (x, y) in [(10, 9), (9, 10), (11, 10), (10, 11)] {
+            state.set(x, y, Some(Color::White));
+        }
+
+        let black = state.territory_border_mask(Color::Black);
+        assert_eq!(black, state.territory(Color::Black));
+        assert_eq!(black.count(), 3 * 19);
+
+        let white = state.territory_border_mask(Color::White);
+        assert_eq!(white.count(), state.territory(Color::White).count() - 1);
+        assert!(!white.get(10, 10));
+        assert!(state.territory(Color::White).get(10, 10));
+    }
+
+    #[test]
+    fn score_area_with_dead() {
+        let mut state = State::default();
+        for y in 0..19 {
+            state.set(3, y, Some(Color::Black));
+            state.set(4, y, Some(Color::White));
+        }
+        state.set(1, 1, Some(Color::White));
+
+        let mut dead = Mask::EMPTY;
+        dead.set(1, 1);
+
+        assert_eq!(state.score_area(0.5), Score::new(19.0, 286.5));
+        assert_eq!(
+            state.score_area_with_dead(dead, 0.5),
+            Score::new(76.0, 285.5)
+        );
+        assert_eq!(
+            state.score_area_with_dead(Mask::EMPTY, 0.5),
+            state.score_area(0.5)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn score_area_with_dead_rejects_empty_points() {
+        let mut dead = Mask::EMPTY;
+        dead.set(0, 0);
+        let _ = State::default().score_area_with_dead(dead, 0.0);
+    }
+
+    #[test]
+    fn is_self_capture_at() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b100,
+            0b100,
+            0b011,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b011,
+            0b001,
+        ]);
+
+        let state = State::from_masks(black, white).unwrap();
+        assert!(state.is_self_capture_at(1, 1, Color::White));
+        assert!(!state.is_self_capture_at(1, 1, Color::Black));
+        assert!(!state.is_self_capture_at(5, 5, Color::White));
+        assert!(!state.is_self_capture_at(0, 0, Color::Black));
+    }
+
+    #[test]
+    fn apply_move() {
+        let mut state: State = [
+            (1, 0, Color::Black),
+            (0, 1, Color::Black),
+            (1, 1, Color::White),
+            (0, 0, Color::White),
+            (2, 0, Color::White),
+        ]
+        .into_iter()
+        .collect();
+
+        let before = state;
+        assert_eq!(state.apply_move(0, 2, Color::White, None), Ok(1));
+        assert_eq!(state.color_at(0, 1), None);
+        assert_eq!(state.color_at(0, 2), Some(Color::White));
+
+        let mut unchanged = before;
+        assert_eq!(
+            unchanged.apply_move(1, 0, Color::White, None),
+            Err(PlaceStoneError::AlreadyExists)
+        );
+        assert_eq!(unchanged, before);
+
+        let mut quiet = state;
+        assert_eq!(quiet.apply_move(5, 5, Color::Black, None), Ok(0));
+        assert_eq!(quiet.black.count(), 2);
+    }
+
+    #[test]
+    fn copy_with_move() {
+        let state: State = [(1, 0, Color::Black), (0, 0, Color::White)]
+            .into_iter()
+            .collect();
+
+        let next = state.copy_with_move(5, 5, Color::White).unwrap();
+        assert_eq!(next.color_at(5, 5), Some(Color::White));
+        assert_eq!(state.color_at(5, 5), None);
+
+        let captured = next.copy_with_move(0, 1, Color::Black).unwrap();
+        assert_eq!(captured.color_at(0, 0), None);
+        assert_eq!(
+            state.copy_with_move(0, 0, Color::Black),
+            Err(PlaceStoneError::AlreadyExists)
+        );
+    }
+
+    #[test]
+    fn is_legal_move() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b0010,
+            0b0101,
+            0b0010,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b0100,
+            0b1000,
+            0b0100,
+        ]);
+
+        let state = State::from_masks(black, white).unwrap();
+        assert!(!state.is_legal_move(0, 1, Color::White, None));
+        assert!(!state.is_legal_move(0, 0, Color::White, None));
+        assert!(state.is_legal_move(1, 1, Color::White, None));
+        assert!(state.is_legal_move(1, 1, Color::Black, None));
+
+        let previous = state;
+        let state = state.play(1, 1, Color::White, None).unwrap();
+        assert!(!state.is_legal_move(2, 1, Color::Black, Some(&previous)));
+        assert!(state.is_legal_move(2, 1, Color::Black, None));
+    }
+
+    #[test]
+    fn hamming_distance() {
+        let a = State::from_masks(Mask::new([0b01]), Mask::new([0b10])).unwrap();
+        let b = State::from_masks(Mask::new([0b10]), Mask::new([0b01])).unwrap();
+        assert_eq!(a.hamming_distance(&b), 4);
+        assert_eq!(a.hamming_distance(&State::default()), 2);
+        assert_eq!(a.hamming_distance(&a), 0);
+    }
+
+    #[test]
+    fn display() {
+        let state: State = [(0, 0, Color::Black), (18, 18, Color::White)]
+            .into_iter()
+            .collect();
+        let display = state.to_string();
+        let lines: Vec<_> = display.lines().collect();
+        assert_eq!(lines.len(), 20);
+        assert_eq!(lines[0], "   A B C D E F G H J K L M N O P Q R S T");
+        assert_eq!(lines[1], "19 X . . . . . . . . . . . . . . . . . .");
+        assert_eq!(lines[19], " 1 . . . . . . . . . . . . . . . . . . O");
+    }
+
+    #[test]
+    fn ladder_check() {
+        let mut state: State = [
+            (5, 5, Color::White),
+            (4, 5, Color::Black),
+            (5, 4, Color::Black),
+            (6, 5, Color::Black),
+            (6, 6, Color::Black),
+        ]
+        .into_iter()
+        .collect();
+        assert!(state.ladder_check(5, 5, Color::White));
+        assert!(!state.ladder_check(4, 5, Color::Black));
+        assert!(!state.ladder_check(9, 9, Color::White));
+
+        let mut broken = state;
+        broken.set(2, 10, Some(Color::White));
+        assert!(!broken.ladder_check(5, 5, Color::White));
+
+        state.set(6, 6, None);
+        assert!(!state.ladder_check(5, 5, Color::White));
+    }
+
+    #[test]
+    fn group_liberties_mask() {
+        let state: State = [
+            (0, 0, Color::Black),
+            (1, 0, Color::Black),
+            (2, 0, Color::White),
+            (0, 1, Color::White),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(state.group_liberties_mask(0, 0), Some(Mask::new([0, 0b10])));
+        assert_eq!(
+            state.group_liberties_mask(2, 0),
+            Some(Mask::new([0b1000, 0b100]))
+        );
+        assert_eq!(state.group_liberties_mask(5, 5), None);
+    }
+
+    #[test]
+    fn total_and_distinct_liberties() {
+        // Two black stones share the liberty at (1, 0)
+        let state: State = [
+            (0, 0, Color::Black),
+            (2, 0, Color::Black),
+            (0, 1, Color::White),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(state.total_liberties(Color::Black), 4);
+        assert_eq!(state.distinct_liberties(Color::Black), 3);
+        assert_eq!(state.total_liberties(Color::White), 2);
+        assert_eq!(state.distinct_liberties(Color::White), 2);
+        assert_eq!(State::default().total_liberties(Color::Black), 0);
+    }
+
+    #[test]
+    fn compact_bytes_round_trip() {
+        let state: State = [
+            (0, 0, Color::Black),
+            (18, 18, Color::Black),
+            (0, 0, Color::Black),
+            (7, 3, Color::White),
+            (18, 18, Color::Black),
+            (17, 18, Color::White),
+        ]
+        .into_iter()
+        .collect();
+
+        let bytes = state.to_compact_bytes();
+        assert_eq!(bytes[0], 0b1);
+        assert_eq!(bytes[360 / 8], 1 << (360 % 8));
+        assert_eq!(bytes[90] >> 2, 0);
+        assert_eq!(State::from_compact_bytes(bytes), Ok(state));
+        assert_eq!(State::from_compact_bytes([0; 91]), Ok(State::default()));
+    }
+
+    #[test]
+    fn compact_bytes_rejects_invalid_input() {
+        let mut bytes = [0; 91];
+        bytes[0] = 0b1;
+        bytes[361 / 8] |= 1 << (361 % 8);
+        assert_eq!(State::from_compact_bytes(bytes), Err(StateError::Overlap));
+
+        let mut bytes = [0; 91];
+        bytes[90] = 1 << 7;
+        assert_eq!(
+            State::from_compact_bytes(bytes),
+            Err(StateError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn legal_moves_mask() {
+        #[rustfmt::skip]
+        let black = Mask::new([
+            0b0010,
+            0b0101,
+            0b0010,
+        ]);
+
+        #[rustfmt::skip]
+        let white = Mask::new([
+            0b0100,
+            0b1000,
+            0b0100,
+        ]);
+
+        let previous = State::from_masks(black, white).unwrap();
+        let state = previous.play(1, 1, Color::White, None).unwrap();
+
+        let legal = state.legal_moves_mask(Color::Black, Some(&previous));
+        assert!(!legal.get(2, 1));
+        assert!(!legal.get(1, 1));
+        assert!(legal.get(0, 0));
+        assert_eq!(legal.count(), 361 - 7 - 1);
+        assert!(legal.is_subset_of(&state.empty()));
+
+        let legal = state.legal_moves_mask(Color::Black, None);
+        assert!(legal.get(2, 1));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{Color, Move, PlaceStoneError, State, Tree};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZobristTable {
+    keys: [[u64; 361]; 2],
+}
+
+impl ZobristTable {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        let mut state = seed;
+        let mut keys = [[0; 361]; 2];
+        for key in keys.iter_mut().flatten() {
+            *key = splitmix64(&mut state);
+        }
+        Self { keys }
+    }
+
+    #[must_use]
+    pub fn key(&self, x: usize, y: usize, color: Color) -> u64 {
+        assert!(x <= 18);
+        assert!(y <= 18);
+        self.keys[color.index()][y * 19 + x]
+    }
+
+    #[must_use]
+    pub fn hash(&self, state: &State) -> u64 {
+        self.update(0, &State::default(), state)
+    }
+
+    // Only the intersections that differ between the two states are visited
+    #[must_use]
+    pub fn update(&self, hash: u64, before: &State, after: &State) -> u64 {
+        let mut hash = hash;
+        for color in [Color::Black, Color::White] {
+            let changed = before.stones(color) ^ after.stones(color);
+            for (x, y) in changed.iter_positions() {
+                hash ^= self.key(x, y, color);
+            }
+        }
+        hash
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl Tree {
+    #[must_use]
+    pub fn current_hash(&self, table: &ZobristTable) -> u64 {
+        table.hash(self.current())
+    }
+
+    // Hashes are cached per node, so a tree should stick to a single table
+    pub fn apply_move_with_hash(
+        &mut self,
+        mv: Move,
+        table: &ZobristTable,
+    ) -> Result<u64, PlaceStoneError> {
+        let before = *self.current();
+        let hash = match self.hashes[self.current] {
+            Some(hash) => {
+                debug_assert_eq!(hash, table.hash(&before));
+                hash
+            }
+            None => table.hash(&before),
+        };
+        self.apply(mv)?;
+        let hash = table.update(hash, &before, self.current());
+        self.hashes[self.current] = Some(hash);
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_keys() {
+        let a = ZobristTable::new(42);
+        let b = ZobristTable::new(42);
+        let c = ZobristTable::new(43);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a.key(0, 0, Color::Black), a.key(0, 0, Color::White));
+        assert_ne!(a.key(0, 0, Color::Black), a.key(1, 0, Color::Black));
+    }
+
+    #[test]
+    fn incremental_hash_matches_full_hash() {
+        let table = ZobristTable::new(7);
+        let mut tree = Tree::empty();
+        assert_eq!(tree.current_hash(&table), 0);
+
+        // White's stone at (0, 0) is captured by the last move
+        let moves = [
+            Move::Place(1, 0),
+            Move::Place(0, 0),
+            Move::Pass,
+            Move::Place(5, 5),
+            Move::Place(0, 1),
+        ];
+        for mv in moves {
+            let hash = tree.apply_move_with_hash(mv, &table).unwrap();
+            assert_eq!(hash, tree.current_hash(&table));
+        }
+        assert_eq!(tree.current().color_at(0, 0), None);
+
+        let expected = table.key(1, 0, Color::Black)
+            ^ table.key(0, 1, Color::Black)
+            ^ table.key(5, 5, Color::White);
+        assert_eq!(tree.current_hash(&table), expected);
+    }
+
+    #[test]
+    fn illegal_move_leaves_tree_unchanged() {
+        let table = ZobristTable::new(7);
+        let mut tree = Tree::empty();
+        tree.apply_move_with_hash(Move::Place(3, 3), &table)
+            .unwrap();
+        assert_eq!(
+            tree.apply_move_with_hash(Move::Place(3, 3), &table),
+            Err(PlaceStoneError::AlreadyExists)
+        );
+        assert_eq!(tree.node_count(), 2);
+    }
+
+    #[test]
+    fn hashes_survive_plain_moves_and_navigation() {
+        let table = ZobristTable::new(7);
+        let mut tree = Tree::empty();
+        tree.apply_move_with_hash(Move::Place(3, 3), &table)
+            .unwrap();
+        tree.place_stone(15, 15).unwrap();
+        let hash = tree
+            .apply_move_with_hash(Move::Place(3, 15), &table)
+            .unwrap();
+        assert_eq!(hash, tree.current_hash(&table));
+
+        tree.go_to(1);
+        let hash = tree
+            .apply_move_with_hash(Move::Place(15, 3), &table)
+            .unwrap();
+        assert_eq!(hash, tree.current_hash(&table));
+    }
+}